@@ -1,12 +1,33 @@
-use crate::{BotState, OPTS};
+use crate::{is_pathfinding, is_pearl_visible_at, BlockPos, BotState, OPTS};
 use azalea::{
+    blocks::Block,
     ecs::query::With,
-    entity::{metadata::Player, Position},
+    entity::{
+        metadata::{AbsorptionAmount, Health, Player},
+        EyeHeight, LookDirection, Physics, Position,
+    },
+    inventory::{
+        operations::{ClickOperation, ThrowClick},
+        ContainerClickEvent, InventoryComponent, ItemSlot,
+    },
+    packet_handling::game::SendPacketEvent,
     pathfinder::goals::{BlockPosGoal, ReachBlockPosGoal},
     prelude::*,
-    world::InstanceName,
-    GameProfileComponent, Vec3,
+    protocol::packets::game::{
+        serverbound_interact_packet::{ActionType, InteractionHand, ServerboundInteractPacket},
+        serverbound_ping_request_packet::ServerboundPingRequestPacket,
+        ServerboundGamePacket,
+    },
+    world::{InstanceName, MinecraftEntityId},
+    GameProfileComponent, Hunger, Vec3,
 };
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// How far away a player may be for !interactplayer to be able to right-click them
+const PLAYER_INTERACT_REACH: f64 = 3.0;
 
 pub fn execute(
     bot: &mut Client,
@@ -15,37 +36,76 @@ pub fn execute(
     mut command: String,
     args: Vec<String>,
 ) -> anyhow::Result<bool> {
-    if command.starts_with('!') {
-        command.remove(0);
+    let prefix = &OPTS.command_prefix;
+    if let Some(unprefixed_command) = command.strip_prefix(prefix.as_str()) {
+        command = unprefixed_command.to_owned();
     }
     command = command.to_lowercase();
     let sender_is_admin = OPTS.admin.iter().any(|a| sender.eq_ignore_ascii_case(a));
 
     match command.as_str() {
         "help" => {
-            let mut commands = vec!["!help", "!about"];
+            let mut commands = vec!["help", "about", "health", "ping"];
             if !OPTS.no_stasis {
-                commands.push("!tp");
+                commands.push("tp");
+                commands.push("stasis-users");
+                commands.push("listpearls");
             }
             if sender_is_admin {
-                commands.append(&mut vec!["!comehere", "!say", "!stop"]);
+                commands.append(&mut vec![
+                    "comehere",
+                    "say",
+                    "stop",
+                    "emergency",
+                    "viewdistance",
+                    "dropitem",
+                    "quiet",
+                    "sysinfo",
+                    "flip",
+                    "movement",
+                    "regen",
+                    "stasis-stats",
+                    "reconnects",
+                    "interactplayer",
+                    "config",
+                    "lastpearl",
+                    "stasis-suspend",
+                    "modules",
+                    "stasis-backup",
+                    "look",
+                    "taskage",
+                    "reconnect-config",
+                    "signinginfo",
+                    "nearestchamber",
+                    "stasis-list",
+                    "whereis",
+                    "errors",
+                ]);
                 if OPTS.enable_pos_command {
-                    commands.push("!pos");
+                    commands.push("pos");
                 }
             }
             if !OPTS.admin.is_empty() {
-                commands.push("!admins");
+                commands.push("admins");
             }
             commands.sort();
 
             send_command(
                 bot,
-                &format!("msg {sender} Commands: {}", commands.join(", ")),
+                bot_state,
+                &format!(
+                    "msg {sender} Commands: {}",
+                    commands
+                        .iter()
+                        .map(|command| format!("{prefix}{command}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
             );
             Ok(true)
         }
         "about" => {
-            send_command(bot, &format!("msg {sender} Hi, I'm running EnderKill98's azalea-based stasis-bot {}: github.com/EnderKill98/stasis-bot", env!("CARGO_PKG_VERSION")));
+            send_command(bot, bot_state, &format!("msg {sender} Hi, I'm running EnderKill98's azalea-based stasis-bot {}: github.com/EnderKill98/stasis-bot", env!("CARGO_PKG_VERSION")));
             Ok(true)
         }
         "tp" => {
@@ -53,21 +113,112 @@ pub fn execute(
             if OPTS.no_stasis {
                 send_command(
                     bot,
+                    bot_state,
                     &format!("msg {sender} I'm not allowed to do pearl duties :(..."),
                 );
                 return Ok(true);
             }
 
+            let suspended_for = {
+                let mut suspended_until = bot_state.suspended_until.lock();
+                suspended_until.retain(|_, until| *until > Instant::now());
+                suspended_until
+                    .get(&sender.to_lowercase())
+                    .map(|until| until.duration_since(Instant::now()))
+            };
+            if let Some(suspended_for) = suspended_for {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!(
+                        "msg {sender} You are temporarily suspended from pulling. Try again in {}.",
+                        format_duration(suspended_for)
+                    ),
+                );
+                bot_state.record_pull_stat(|stats| stats.refused_other += 1);
+                return Ok(true);
+            }
+
             if let Some(trapdoor_pos) = remembered_trapdoor_positions.get(&sender) {
                 if bot_state.pathfinding_requested_by.lock().is_some() {
-                    send_command(bot, &format!("msg {sender} Please ask again in a bit. I'm currently already going somewhere..."));
+                    send_command(bot, bot_state, &format!("msg {sender} Please ask again in a bit. I'm currently already going somewhere..."));
+                    bot_state.record_pull_stat(|stats| stats.refused_busy += 1);
+                    return Ok(true);
+                }
+
+                if OPTS.tp_cooldown_secs > 0
+                    && let Some(last_started) = bot_state
+                        .last_pull_started
+                        .lock()
+                        .get(&sender.to_lowercase())
+                    && last_started.elapsed() < Duration::from_secs(OPTS.tp_cooldown_secs)
+                {
+                    let wait = Duration::from_secs(OPTS.tp_cooldown_secs)
+                        .saturating_sub(last_started.elapsed());
+                    send_command(
+                        bot,
+                        bot_state,
+                        &format!(
+                            "msg {sender} You just got pulled. Please wait {} before asking again.",
+                            format_duration(wait)
+                        ),
+                    );
+                    bot_state.record_pull_stat(|stats| stats.refused_other += 1);
+                    return Ok(true);
+                }
+
+                if let Some(radius) = OPTS.already_here_radius {
+                    let sender_entity = bot.entity_by::<With<Player>, (&GameProfileComponent,)>(
+                        |(profile,): &(&GameProfileComponent,)| profile.name == sender,
+                    );
+                    if let Some(sender_entity) = sender_entity {
+                        let sender_pos = bot.entity_component::<Position>(sender_entity);
+                        let trapdoor_center = Vec3::new(
+                            trapdoor_pos.x as f64 + 0.5,
+                            trapdoor_pos.y as f64 + 0.5,
+                            trapdoor_pos.z as f64 + 0.5,
+                        );
+                        if sender_pos.distance_to_sqr(&trapdoor_center) <= radius * radius {
+                            send_command(
+                                bot,
+                                bot_state,
+                                &format!("msg {sender} You're already here!"),
+                            );
+                            bot_state.record_pull_stat(|stats| stats.refused_other += 1);
+                            return Ok(true);
+                        }
+                    }
+                }
+                if OPTS.verify_pearl && is_pearl_visible_at(bot, *trapdoor_pos) == Some(false) {
+                    send_command(
+                        bot,
+                        bot_state,
+                        &format!("msg {sender} I don't see a pearl in your stasis chamber. Sorry!"),
+                    );
+                    bot_state.record_pull_stat(|stats| stats.refused_other += 1);
                     return Ok(true);
                 }
                 send_command(
                     bot,
-                    &format!("msg {sender} Walking to your stasis chamber..."),
+                    bot_state,
+                    &format!(
+                        "msg {sender} Walking to your stasis chamber{}...",
+                        if OPTS.stasis_dry_run {
+                            " (dry run, won't pull)"
+                        } else {
+                            ""
+                        }
+                    ),
                 );
 
+                // Dry runs would skew the success rate
+                if !OPTS.stasis_dry_run {
+                    bot_state.record_pull_stat(|stats| stats.started += 1);
+                }
+                bot_state
+                    .last_pull_started
+                    .lock()
+                    .insert(sender.to_lowercase(), Instant::now());
                 *bot_state.return_to_after_pulled.lock() =
                     Some(Vec3::from(&bot.entity_component::<Position>(bot.entity)));
 
@@ -76,24 +227,108 @@ pub fn execute(
                     pos: azalea::BlockPos::from(*trapdoor_pos),
                     chunk_storage: bot.world().read().chunks.clone(),
                 };
-                if OPTS.no_mining {
-                    bot.goto_without_mining(goal);
-                } else {
-                    bot.goto(goal);
-                }
+                crate::goto(bot, bot_state, goal);
                 *bot_state.pathfinding_requested_by.lock() = Some(sender.clone());
+                *bot_state.pathfinding_requested_at.lock() = Some(Instant::now());
             } else {
                 send_command(
                     bot,
+                    bot_state,
                     &format!("msg {sender} I'm not aware whether you have a pearl here. Sorry!"),
                 );
+                bot_state.record_pull_stat(|stats| stats.refused_unknown += 1);
             }
 
             Ok(true)
         }
+        "listpearls" => {
+            if OPTS.no_stasis {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} I'm not allowed to do pearl duties :(..."),
+                );
+                return Ok(true);
+            }
+
+            let player = args.first().unwrap_or(&sender);
+            if !player.eq_ignore_ascii_case(&sender) && !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to look up the pearls of other players!"));
+                return Ok(true);
+            }
+
+            let chamber = bot_state
+                .remembered_trapdoor_positions
+                .lock()
+                .iter()
+                .find(|(owner, _)| owner.eq_ignore_ascii_case(player))
+                .map(|(owner, trapdoor_pos)| (owner.clone(), *trapdoor_pos));
+            let Some((owner, trapdoor_pos)) = chamber else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} I'm not aware of any pearl of {player}."),
+                );
+                return Ok(true);
+            };
+
+            let pearl = match is_pearl_visible_at(bot, trapdoor_pos) {
+                Some(true) => "pearl is visible",
+                Some(false) => "pearl is NOT visible",
+                None => "chamber is not loaded",
+            };
+            send_command(
+                bot,
+                bot_state,
+                &format!("msg {sender} {owner} has 1 stasis chamber here ({pearl})."),
+            );
+            Ok(true)
+        }
+        "health" => {
+            // TODO: Use Attribute::GenericMaxHealth instead of hardcoded 20
+            let health = *bot.component::<Health>();
+            let absorption = *bot.component::<AbsorptionAmount>();
+            let hunger = bot.component::<Hunger>();
+            let mut message = format!(
+                "HP: {health:.01}/20, Food: {}, Sat: {:.01}",
+                hunger.food, hunger.saturation
+            );
+            if absorption > 0.0 {
+                message += &format!(", Absorption: {absorption:.01}");
+            }
+            if let Some(autolog_hp) = OPTS.autolog_hp {
+                message += &format!(" (Autolog at {autolog_hp:.01} HP)");
+            }
+            send_command(bot, bot_state, &format!("msg {sender} {message}"));
+            Ok(true)
+        }
+        "ping" => {
+            if bot_state.pending_ping.lock().is_some() {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} I'm already measuring the ping. Please wait a bit."),
+                );
+                return Ok(true);
+            }
+
+            // Current time as id, so it's different every time
+            let id = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            *bot_state.pending_ping.lock() = Some((id, sender.clone(), Instant::now()));
+            bot.ecs.lock().send_event(SendPacketEvent {
+                entity: bot.entity,
+                packet: ServerboundGamePacket::PingRequest(ServerboundPingRequestPacket {
+                    time: id,
+                }),
+            });
+            Ok(true)
+        }
         "comehere" => {
             if !sender_is_admin {
-                send_command(bot, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
                 return Ok(true);
             }
 
@@ -102,23 +337,35 @@ pub fn execute(
             );
             if let Some(sender_entity) = sender_entity {
                 let position = bot.entity_component::<Position>(sender_entity);
+                if OPTS.comehere_max_dist > 0.0 {
+                    let max_dist = OPTS.comehere_max_dist;
+                    let my_pos = bot.entity_component::<Position>(bot.entity);
+                    let horizontal_distance =
+                        ((position.x - my_pos.x).powi(2) + (position.z - my_pos.z).powi(2)).sqrt();
+                    if horizontal_distance > max_dist {
+                        send_command(
+                            bot,
+                            bot_state,
+                            &format!("msg {sender} You're {horizontal_distance:.0} blocks away. That's too far (max {max_dist:.0})!"),
+                        );
+                        return Ok(true);
+                    }
+                }
                 let goal = BlockPosGoal(azalea::BlockPos {
                     x: position.x.floor() as i32,
                     y: position.y.floor() as i32,
                     z: position.z.floor() as i32,
                 });
-                if OPTS.no_mining {
-                    bot.goto_without_mining(goal);
-                } else {
-                    bot.goto(goal)
-                }
+                crate::goto(bot, bot_state, goal);
                 send_command(
                     bot,
+                    bot_state,
                     &format!("msg {sender} Walking to your block position..."),
                 );
             } else {
                 send_command(
                     bot,
+                    bot_state,
                     &format!("msg {sender} I could not find you in my render distance!"),
                 );
             }
@@ -127,13 +374,14 @@ pub fn execute(
         "admins" => {
             send_command(
                 bot,
+                bot_state,
                 &format!("msg {sender} Admins: {}", OPTS.admin.join(", ")),
             );
             Ok(true)
         }
         "say" => {
             if !sender_is_admin {
-                send_command(bot, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
                 return Ok(true);
             }
 
@@ -149,20 +397,973 @@ pub fn execute(
         }
         "stop" => {
             if !sender_is_admin {
-                send_command(bot, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
                 return Ok(true);
             }
 
             info!("Stopping... Bye!");
-            std::process::exit(crate::EXITCODE_USER_REQUESTED_STOP);
+            crate::disconnect_and_exit(bot, crate::EXITCODE_USER_REQUESTED_STOP);
+            Ok(true)
+        }
+        "emergency" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let health = *bot.component::<Health>();
+            if let Some(autolog_hp) = OPTS.autolog_hp {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!(
+                        "msg {sender} Emergency quit is armed at {autolog_hp:.02} HP (or a totem pop). I'm at {health:.02} HP, which is {:.02} HP above it.",
+                        health - autolog_hp
+                    ),
+                );
+            } else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} Emergency quit is not enabled (see --autolog-hp). I'm at {health:.02} HP."),
+                );
+            }
+            Ok(true)
+        }
+        "viewdistance" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let format_distance = |distance: Option<u32>| match distance {
+                Some(distance) => format!("{distance} chunks"),
+                None => "unknown".to_owned(),
+            };
+            let view_distance = format_distance(*bot_state.server_view_distance.lock());
+            let simulation_distance =
+                format_distance(*bot_state.server_simulation_distance.lock());
+            send_command(
+                bot,
+                bot_state,
+                &format!("msg {sender} View distance: {view_distance}, Simulation distance: {simulation_distance}"),
+            );
+            Ok(true)
+        }
+        "dropitem" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let Some(item_name) = args.first() else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} Usage: {prefix}dropitem <item> [count]"),
+                );
+                return Ok(true);
+            };
+            let item_name = item_name
+                .to_lowercase()
+                .trim_start_matches("minecraft:")
+                .to_owned();
+            let count = match args.get(1).map(|count| count.parse::<u32>()) {
+                Some(Ok(count)) => count,
+                Some(Err(_)) => {
+                    send_command(
+                        bot,
+                        bot_state,
+                        &format!("msg {sender} Usage: {prefix}dropitem <item> [count]"),
+                    );
+                    return Ok(true);
+                }
+                None => 1,
+            };
+
+            let inv = bot.entity_component::<InventoryComponent>(bot.entity);
+            let inv_menu = inv.inventory_menu;
+            let mut remaining = count;
+            let mut operations = Vec::new();
+            for slot in inv_menu.player_slots_range() {
+                if remaining == 0 {
+                    break;
+                }
+                let Some(ItemSlot::Present(item_slot)) = inv_menu.slot(slot) else {
+                    continue;
+                };
+                if item_slot.kind.to_string().trim_start_matches("minecraft:") != item_name {
+                    continue;
+                }
+
+                let stack_count = item_slot.count.max(0) as u32;
+                if remaining >= stack_count {
+                    operations.push(ClickOperation::Throw(ThrowClick::All { slot: slot as u16 }));
+                    remaining -= stack_count;
+                } else {
+                    for _ in 0..remaining {
+                        operations
+                            .push(ClickOperation::Throw(ThrowClick::Single { slot: slot as u16 }));
+                    }
+                    remaining = 0;
+                }
+            }
+
+            let dropped = count - remaining;
+            info!("Dropping {dropped}x {item_name} as requested by {sender}...");
+            {
+                let entity = bot.entity;
+                let mut ecs = bot.ecs.lock();
+                for operation in operations {
+                    ecs.send_event(ContainerClickEvent {
+                        entity,
+                        window_id: 0,
+                        operation,
+                    });
+                }
+            }
+            send_command(
+                bot,
+                bot_state,
+                &format!("msg {sender} Dropped {dropped} of {count} requested {item_name}."),
+            );
+            Ok(true)
+        }
+        "quiet" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            match args.first().map(|arg| arg.to_lowercase()).as_deref() {
+                Some("on") => {
+                    send_command(bot, bot_state, &format!("msg {sender} Quiet mode enabled. I won't send any messages until it's turned off again."));
+                    *bot_state.quiet_override.lock() = Some(true);
+                    info!("Quiet mode was enabled by {sender}.");
+                }
+                Some("off") => {
+                    *bot_state.quiet_override.lock() = Some(false);
+                    info!("Quiet mode was disabled by {sender}.");
+                    send_command(
+                        bot,
+                        bot_state,
+                        &format!("msg {sender} Quiet mode disabled."),
+                    );
+                }
+                _ => {
+                    let state = if bot_state.is_quiet() { "on" } else { "off" };
+                    send_command(
+                        bot,
+                        bot_state,
+                        &format!("msg {sender} Quiet mode is {state}. Usage: {prefix}quiet on|off"),
+                    );
+                }
+            }
+            Ok(true)
+        }
+        "sysinfo" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            // Only available on linux. Other platforms will just report it as unavailable.
+            let memory = proc_status_field("VmRSS").unwrap_or_else(|| "unavailable".to_owned());
+            let threads = proc_status_field("Threads").unwrap_or_else(|| "unavailable".to_owned());
+            send_command(
+                bot,
+                bot_state,
+                &format!("msg {sender} Memory: {memory}, Threads: {threads}"),
+            );
+            Ok(true)
+        }
+        "flip" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let coords = args
+                .iter()
+                .map(|arg| arg.parse::<i32>())
+                .collect::<Result<Vec<_>, _>>();
+            let Ok(&[x, y, z]) = coords.as_deref() else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} Usage: {prefix}flip <x> <y> <z>"),
+                );
+                return Ok(true);
+            };
+            if bot_state.pathfinding_requested_by.lock().is_some() {
+                send_command(bot, bot_state, &format!("msg {sender} Please ask again in a bit. I'm currently already going somewhere..."));
+                return Ok(true);
+            }
+
+            let flip_pos = BlockPos { x, y, z };
+            send_command(
+                bot,
+                bot_state,
+                &format!("msg {sender} Walking to {x} {y} {z} to flip it..."),
+            );
+            info!("Walking to {flip_pos:?} to flip it...");
+            *bot_state.pending_flip.lock() = Some(flip_pos);
+            let goal = ReachBlockPosGoal {
+                pos: azalea::BlockPos::from(flip_pos),
+                chunk_storage: bot.world().read().chunks.clone(),
+            };
+            crate::goto(bot, bot_state, goal);
+            *bot_state.pathfinding_requested_by.lock() = Some(sender.clone());
+            *bot_state.pathfinding_requested_at.lock() = Some(Instant::now());
+            Ok(true)
+        }
+        "movement" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let physics = bot.component::<Physics>();
+            let speed = physics.velocity.length();
+            let yes_no = |value: bool| if value { "yes" } else { "no" };
+            send_command(
+                bot,
+                bot_state,
+                &format!(
+                    "msg {sender} Speed: {speed:.03} blocks/tick ({:.02} blocks/s), On ground: {}, Pathfinding: {}",
+                    speed * 20.0,
+                    yes_no(physics.on_ground),
+                    yes_no(is_pathfinding(bot)),
+                ),
+            );
+            Ok(true)
+        }
+        "regen" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            // TODO: Use Attribute::GenericMaxHealth instead of hardcoded 20
+            let max_health = 20f32;
+            let health = *bot.component::<Health>();
+            let hunger = bot.component::<Hunger>();
+            let missing_health = (max_health - health).max(0.0);
+
+            // Based on vanilla natural regeneration: With full food and saturation left, heal up
+            // to 1 HP every 10 ticks (using saturation). Otherwise with at least 18 food,
+            // heal 1 HP every 80 ticks.
+            let hp_per_sec = if hunger.food >= 20 && hunger.saturation > 0.0 {
+                Some(hunger.saturation.min(6.0) / 6.0 * 2.0)
+            } else if hunger.food >= 18 {
+                Some(1.0 / 4.0)
+            } else {
+                None
+            };
+
+            let state = if missing_health <= 0.0 {
+                "Already at full HP".to_owned()
+            } else if let Some(hp_per_sec) = hp_per_sec {
+                format!(
+                    "Regenerating, full HP in ~{:.0}s",
+                    missing_health / hp_per_sec
+                )
+            } else {
+                "Not regenerating (food too low)".to_owned()
+            };
+            send_command(
+                bot,
+                bot_state,
+                &format!(
+                    "msg {sender} HP: {health:.01}/{max_health:.0}, Food: {}, Sat: {:.01}. {state}.",
+                    hunger.food, hunger.saturation
+                ),
+            );
+            Ok(true)
+        }
+        "stasis-stats" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let stats = bot_state.pull_stats.lock().clone();
+            let success_rate = if stats.started > 0 {
+                format!(
+                    "{:.01}%",
+                    stats.succeeded as f64 / stats.started as f64 * 100.0
+                )
+            } else {
+                "n/a".to_owned()
+            };
+            send_command(
+                bot,
+                bot_state,
+                &format!(
+                    "msg {sender} Pulls started: {}, succeeded: {} ({success_rate}), cancelled: {}, refused (busy): {}, refused (no pearl known): {}, refused (other): {}",
+                    stats.started, stats.succeeded, stats.cancelled, stats.refused_busy, stats.refused_unknown, stats.refused_other
+                ),
+            );
+            Ok(true)
+        }
+        "reconnects" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let mut reconnect_stats = crate::RECONNECT_STATS.lock();
+            let message = format!(
+                "Reconnects: {}, Failed attempts in a row: {}",
+                reconnect_stats.total_reconnects, reconnect_stats.consecutive_failures
+            );
+            if args
+                .first()
+                .is_some_and(|arg| arg.eq_ignore_ascii_case("reset"))
+            {
+                reconnect_stats.total_reconnects = 0;
+                reconnect_stats.consecutive_failures = 0;
+                drop(reconnect_stats);
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} {message} (now reset)"),
+                );
+            } else {
+                drop(reconnect_stats);
+                send_command(bot, bot_state, &format!("msg {sender} {message}"));
+            }
+            Ok(true)
+        }
+        "reconnect-config" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            if !args.is_empty() {
+                let delays = args
+                    .iter()
+                    .map(|arg| arg.parse::<u64>())
+                    .collect::<Result<Vec<_>, _>>();
+                let Ok(&[initial_delay_secs, max_delay_secs]) = delays.as_deref() else {
+                    send_command(
+                        bot,
+                        bot_state,
+                        &format!("msg {sender} Usage: {prefix}reconnect-config [<initial secs> <max secs>]"),
+                    );
+                    return Ok(true);
+                };
+                if initial_delay_secs == 0 || max_delay_secs < initial_delay_secs {
+                    send_command(
+                        bot,
+                        bot_state,
+                        &format!("msg {sender} The initial delay must be at least 1 and not more than the max delay!"),
+                    );
+                    return Ok(true);
+                }
+                let mut reconnect_config = crate::RECONNECT_CONFIG.lock();
+                reconnect_config.initial_delay_secs = initial_delay_secs;
+                reconnect_config.max_delay_secs = max_delay_secs;
+                info!("{sender} changed reconnect delays to {initial_delay_secs}s initially and {max_delay_secs}s max.");
+            }
+
+            let (initial_delay_secs, max_delay_secs, multiplier) = {
+                let reconnect_config = crate::RECONNECT_CONFIG.lock();
+                (
+                    reconnect_config.initial_delay_secs,
+                    reconnect_config.max_delay_secs,
+                    reconnect_config.multiplier,
+                )
+            };
+            send_command(
+                bot,
+                bot_state,
+                &format!(
+                    "msg {sender} Reconnect delay: {} initially, growing {multiplier}x up to {}. Applies to the next reconnect.",
+                    format_duration(Duration::from_secs(initial_delay_secs)),
+                    format_duration(Duration::from_secs(max_delay_secs))
+                ),
+            );
+            Ok(true)
+        }
+        "signinginfo" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            if !OPTS.sign_chat {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} Chat signing is disabled (enable with --sign-chat)."),
+                );
+                return Ok(true);
+            }
+
+            let expire_at = *crate::CHAT_CERTS_EXPIRE_AT.lock();
+            let certs_info = match expire_at {
+                Some(expire_at) => {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs() as i64;
+                    if expire_at > now {
+                        format!(
+                            "Certs expire in {}.",
+                            format_duration(Duration::from_secs((expire_at - now) as u64))
+                        )
+                    } else {
+                        format!(
+                            "Certs expired {} ago!",
+                            format_duration(Duration::from_secs((now - expire_at) as u64))
+                        )
+                    }
+                }
+                None => "No certs present!".to_owned(),
+            };
+            send_command(
+                bot,
+                bot_state,
+                &format!("msg {sender} Chat signing is enabled. {certs_info}"),
+            );
+            Ok(true)
+        }
+        "nearestchamber" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let my_pos = *bot.entity_component::<Position>(bot.entity);
+            let nearest = bot_state
+                .remembered_trapdoor_positions
+                .lock()
+                .iter()
+                .map(|(owner, trapdoor_pos)| {
+                    let trapdoor_center = Vec3::new(
+                        trapdoor_pos.x as f64 + 0.5,
+                        trapdoor_pos.y as f64 + 0.5,
+                        trapdoor_pos.z as f64 + 0.5,
+                    );
+                    (
+                        my_pos.distance_to_sqr(&trapdoor_center).sqrt(),
+                        owner.clone(),
+                        *trapdoor_pos,
+                    )
+                })
+                .min_by(|(a, _, _), (b, _, _)| a.total_cmp(b));
+            let Some((distance, owner, trapdoor_pos)) = nearest else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} I don't know of any stasis chambers."),
+                );
+                return Ok(true);
+            };
+
+            let block_id = bot
+                .world()
+                .read()
+                .get_block_state(&azalea::BlockPos::from(trapdoor_pos))
+                .map(|state| Box::<dyn Block>::from(state).id().to_owned());
+            let state = match block_id {
+                Some(id) if id.ends_with("_trapdoor") => {
+                    match is_pearl_visible_at(bot, trapdoor_pos) {
+                        Some(true) => "trapdoor with pearl",
+                        _ => "trapdoor without visible pearl",
+                    }
+                }
+                Some(_) => "no trapdoor anymore",
+                None => "not loaded",
+            };
+            // Coordinates are only revealed, if !pos is allowed as well
+            let location = if OPTS.enable_pos_command {
+                format!(
+                    " at {} {} {}",
+                    trapdoor_pos.x, trapdoor_pos.y, trapdoor_pos.z
+                )
+            } else {
+                String::new()
+            };
+            send_command(
+                bot,
+                bot_state,
+                &format!(
+                    "msg {sender} Nearest chamber belongs to {owner}{location} ({distance:.1} blocks away, {state})."
+                ),
+            );
+            Ok(true)
+        }
+        "stasis-list" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let my_pos = *bot.entity_component::<Position>(bot.entity);
+            let mut chambers = bot_state
+                .remembered_trapdoor_positions
+                .lock()
+                .iter()
+                .map(|(owner, trapdoor_pos)| (owner.clone(), *trapdoor_pos))
+                .collect::<Vec<_>>();
+            if chambers.is_empty() {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} I don't know of any stasis chambers."),
+                );
+                return Ok(true);
+            }
+            chambers.sort_by_key(|(owner, _)| owner.to_lowercase());
+
+            let parts = chambers
+                .iter()
+                .map(|(owner, trapdoor_pos)| {
+                    let trapdoor_center = Vec3::new(
+                        trapdoor_pos.x as f64 + 0.5,
+                        trapdoor_pos.y as f64 + 0.5,
+                        trapdoor_pos.z as f64 + 0.5,
+                    );
+                    let distance = my_pos.distance_to_sqr(&trapdoor_center).sqrt();
+                    // Coordinates are only revealed, if !pos is allowed as well
+                    if OPTS.enable_pos_command {
+                        format!(
+                            "{owner} @ {} {} {} ({distance:.0}m)",
+                            trapdoor_pos.x, trapdoor_pos.y, trapdoor_pos.z
+                        )
+                    } else {
+                        format!("{owner} ({distance:.0}m)")
+                    }
+                })
+                .collect::<Vec<_>>();
+            let title = format!("{} stasis chambers: ", parts.len());
+            send_chunked(bot, bot_state, &sender, &title, &parts);
+            Ok(true)
+        }
+        "whereis" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let Some(player) = args.first() else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} Usage: {prefix}whereis <player>"),
+                );
+                return Ok(true);
+            };
+            let player_entity = bot.entity_by::<With<Player>, (&GameProfileComponent,)>(
+                |(profile,): &(&GameProfileComponent,)| profile.name.eq_ignore_ascii_case(player),
+            );
+            let Some(player_entity) = player_entity else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} {player} is out of my render distance."),
+                );
+                return Ok(true);
+            };
+
+            let name = bot
+                .entity_component::<GameProfileComponent>(player_entity)
+                .name
+                .clone();
+            let pos = bot.entity_component::<Position>(player_entity);
+            let world_name = bot.entity_component::<InstanceName>(player_entity);
+            let my_pos = bot.entity_component::<Position>(bot.entity);
+            let horizontal_distance =
+                ((pos.x - my_pos.x).powi(2) + (pos.z - my_pos.z).powi(2)).sqrt();
+            send_command(
+                bot,
+                bot_state,
+                &format!(
+                    "msg {sender} {name} is at {} {} {} in {} ({horizontal_distance:.1} blocks away).",
+                    pos.x.floor() as i32,
+                    pos.y.floor() as i32,
+                    pos.z.floor() as i32,
+                    world_name.path
+                ),
+            );
+            Ok(true)
+        }
+        "interactplayer" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let Some(target) = args.first().cloned() else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} Usage: {prefix}interactplayer <player>"),
+                );
+                return Ok(true);
+            };
+            let target_entity = bot.entity_by::<With<Player>, (&GameProfileComponent,)>(
+                |(profile,): &(&GameProfileComponent,)| profile.name.eq_ignore_ascii_case(&target),
+            );
+            let Some(target_entity) = target_entity else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} I could not find {target} in my render distance!"),
+                );
+                return Ok(true);
+            };
+
+            let target_pos = bot.entity_component::<Position>(target_entity);
+            let distance = bot
+                .entity_component::<Position>(bot.entity)
+                .distance_to_sqr(&target_pos)
+                .sqrt();
+            if distance > PLAYER_INTERACT_REACH {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} {target} is {distance:.01} blocks away. That's too far to reach!"),
+                );
+                return Ok(true);
+            }
+
+            let target_eye_height = *bot.entity_component::<EyeHeight>(target_entity) as f64;
+            let target_entity_id = bot.entity_component::<MinecraftEntityId>(target_entity);
+            bot.look_at(*target_pos + Vec3::new(0f64, target_eye_height, 0f64));
+            bot.ecs.lock().send_event(SendPacketEvent {
+                entity: bot.entity,
+                packet: ServerboundGamePacket::Interact(ServerboundInteractPacket {
+                    entity_id: target_entity_id.0,
+                    action: ActionType::Interact {
+                        hand: InteractionHand::MainHand,
+                    },
+                    using_secondary_action: false,
+                }),
+            });
+
+            info!("Interacted with player {target} ({distance:.02} blocks away).");
+            send_command(
+                bot,
+                bot_state,
+                &format!("msg {sender} Right-clicked {target}, who is {distance:.01} blocks away from me."),
+            );
+            Ok(true)
+        }
+        "config" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            send_chunked(bot, bot_state, &sender, "Config: ", &OPTS.describe());
+            Ok(true)
+        }
+        "lastpearl" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let last_pearl_throw = bot_state.last_pearl_throw.lock().clone();
+            if let Some((thrower, at)) = last_pearl_throw {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!(
+                        "msg {sender} {thrower} threw the last pearl into a stasis chamber {} ago.",
+                        format_duration(at.elapsed())
+                    ),
+                );
+            } else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} I haven't seen anyone throw a pearl into a stasis chamber, yet."),
+                );
+            }
+            Ok(true)
+        }
+        "stasis-suspend" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let minutes = match args.get(1).map(|minutes| minutes.parse::<u64>()) {
+                Some(Ok(minutes)) => Some(minutes),
+                None => Some(30),
+                Some(Err(_)) => None,
+            };
+            let (Some(player), Some(minutes)) = (args.first(), minutes) else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} Usage: {prefix}stasis-suspend <player> [minutes (default: 30, 0 to lift)]"),
+                );
+                return Ok(true);
+            };
+
+            let mut suspended_until = bot_state.suspended_until.lock();
+            if minutes == 0 {
+                suspended_until.remove(&player.to_lowercase());
+                drop(suspended_until);
+                info!("{sender} lifted the pull suspension of {player}.");
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} {player} can pull again."),
+                );
+            } else {
+                suspended_until.insert(
+                    player.to_lowercase(),
+                    Instant::now() + Duration::from_secs(minutes * 60),
+                );
+                drop(suspended_until);
+                info!("{sender} suspended {player} from pulling for {minutes} minutes.");
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} {player} can't pull for the next {minutes} minutes."),
+                );
+            }
+            Ok(true)
+        }
+        "modules" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let modules = [
+                ("Stasis", !OPTS.no_stasis, "disabled by --no-stasis"),
+                ("AutoEat", OPTS.auto_eat, "enable with --auto-eat"),
+                (
+                    "CombatEat",
+                    OPTS.combat_eat.is_some(),
+                    "enable with --combat-eat",
+                ),
+                (
+                    "AutoLog",
+                    OPTS.autolog_hp.is_some(),
+                    "enable with --autolog-hp",
+                ),
+                (
+                    "LookAtPlayers",
+                    OPTS.look_at_players.is_some(),
+                    "enable with --look-at-players",
+                ),
+                (
+                    "PathfindingSwing",
+                    OPTS.swing_while_pathfinding.is_some(),
+                    "enable with --swing-while-pathfinding",
+                ),
+                ("Greeter", OPTS.greet.is_some(), "enable with --greet"),
+                (
+                    "IdleWander",
+                    OPTS.idle_wander_secs.is_some(),
+                    "enable with --idle-wander-secs",
+                ),
+                (
+                    "KeepAlive",
+                    OPTS.keepalive_message.is_some(),
+                    "enable with --keepalive-message",
+                ),
+                (
+                    "PullButton",
+                    OPTS.pull_button.is_some(),
+                    "enable with --pull-button",
+                ),
+                (
+                    "AutoCloseContainer",
+                    OPTS.auto_close_container_secs.is_some(),
+                    "enable with --auto-close-container-secs",
+                ),
+                (
+                    "PosCommand",
+                    OPTS.enable_pos_command,
+                    "enable with --enable-pos-command",
+                ),
+                ("ViaProxy", OPTS.via.is_some(), "enable with --via"),
+                ("OpenAuthMod", OPTS.openauthmod, "enable with --openauthmod"),
+            ];
+            let (enabled, disabled): (Vec<_>, Vec<_>) =
+                modules.iter().partition(|(_, enabled, _)| *enabled);
+            let enabled = enabled
+                .iter()
+                .map(|(name, _, _)| name.to_string())
+                .collect::<Vec<_>>();
+            let disabled = disabled
+                .iter()
+                .map(|(name, _, hint)| format!("{name} ({hint})"))
+                .collect::<Vec<_>>();
+            send_chunked(bot, bot_state, &sender, "Enabled: ", &enabled);
+            send_chunked(bot, bot_state, &sender, "Disabled: ", &disabled);
+            Ok(true)
+        }
+        "stasis-backup" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let path = BotState::remembered_trapdoor_positions_path();
+            if !path.is_file() {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} There is no file with remembered trapdoor positions to backup, yet."),
+                );
+                return Ok(true);
+            }
+
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let mut backup_path = path.clone().into_os_string();
+            backup_path.push(format!(".{timestamp}.bak"));
+            let backup_path = PathBuf::from(backup_path);
+            match std::fs::copy(&path, &backup_path) {
+                Ok(_) => {
+                    info!("Backed up {path:?} to {backup_path:?} as requested by {sender}.");
+                    send_command(
+                        bot,
+                        bot_state,
+                        &format!("msg {sender} Saved backup as {}", backup_path.display()),
+                    );
+                }
+                Err(err) => {
+                    error!("Failed to backup {path:?} to {backup_path:?}: {err:?}");
+                    send_command(
+                        bot,
+                        bot_state,
+                        &format!("msg {sender} Failed to create a backup: {err}"),
+                    );
+                }
+            }
+            Ok(true)
+        }
+        "look" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let direction = bot.component::<LookDirection>();
+            send_command(
+                bot,
+                bot_state,
+                &format!(
+                    "msg {sender} Yaw: {:.02}, Pitch: {:.02}",
+                    direction.y_rot, direction.x_rot
+                ),
+            );
+            Ok(true)
+        }
+        "taskage" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let requested_by = bot_state.pathfinding_requested_by.lock().clone();
+            let requested_at = *bot_state.pathfinding_requested_at.lock();
+            if let Some(requested_by) = requested_by
+                && let Some(requested_at) = requested_at
+            {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!(
+                        "msg {sender} I've been busy with a request of {requested_by} for {}.",
+                        format_duration(requested_at.elapsed())
+                    ),
+                );
+            } else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} I'm not busy with any request right now."),
+                );
+            }
+            Ok(true)
+        }
+        "errors" => {
+            if !sender_is_admin {
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                return Ok(true);
+            }
+
+            let mut errors = crate::LAST_ERRORS
+                .lock()
+                .iter()
+                .map(|(event_name, (message, at))| (*event_name, message.clone(), *at))
+                .collect::<Vec<_>>();
+            if errors.is_empty() {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} No errors while handling events so far."),
+                );
+                return Ok(true);
+            }
+            // Most recent first
+            errors.sort_by_key(|(_, _, at)| std::cmp::Reverse(*at));
+            let errors = errors
+                .into_iter()
+                .map(|(event_name, message, at)| {
+                    format!(
+                        "{event_name} ({} ago): {message}",
+                        format_duration(at.elapsed())
+                    )
+                })
+                .collect::<Vec<_>>();
+            send_chunked(bot, bot_state, &sender, "Last errors: ", &errors);
+            Ok(true)
+        }
+        "stasis-users" => {
+            if OPTS.no_stasis {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!("msg {sender} I'm not allowed to do pearl duties :(..."),
+                );
+                return Ok(true);
+            }
+
+            let mut users = bot_state
+                .remembered_trapdoor_positions
+                .lock()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>();
+            users.sort_by_key(|user| user.to_lowercase());
+            if sender_is_admin && !users.is_empty() {
+                let title = format!("{} players have a stasis chamber here: ", users.len());
+                send_chunked(bot, bot_state, &sender, &title, &users);
+            } else {
+                send_command(
+                    bot,
+                    bot_state,
+                    &format!(
+                        "msg {sender} {} players have a stasis chamber here.",
+                        users.len()
+                    ),
+                );
+            }
+            Ok(true)
         }
         "pos" => {
             if !sender_is_admin {
-                send_command(bot, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but you need to be specified as an admin to use this command!"));
                 return Ok(true);
             }
             if !OPTS.enable_pos_command {
-                send_command(bot, &format!("msg {sender} Sorry, but this command was not enabled. The owner needs to add the flag --enable-pos-command in order to do so!"));
+                send_command(bot, bot_state, &format!("msg {sender} Sorry, but this command was not enabled. The owner needs to add the flag --enable-pos-command in order to do so!"));
                 return Ok(true);
             }
 
@@ -170,6 +1371,7 @@ pub fn execute(
             let world_name = bot.component::<InstanceName>();
             send_command(
                 bot,
+                bot_state,
                 &format!(
                     "msg {sender} I'm at {:.03} {:.03} {:.03} in {}",
                     pos.x, pos.y, pos.z, world_name.path,
@@ -182,8 +1384,61 @@ pub fn execute(
     }
 }
 
-pub fn send_command(bot: &mut Client, command: &str) {
-    if OPTS.quiet {
+/// Send the parts as messages to the player, spreading them across
+/// as many messages as needed to not exceed the chat length limit.
+pub fn send_chunked(
+    bot: &mut Client,
+    bot_state: &BotState,
+    receiver: &str,
+    title: &str,
+    parts: &[String],
+) {
+    // Chat messages are limited to 256 characters, including "msg <name> "
+    let max_len = 256 - "msg  ".len() - receiver.len();
+    let mut message = title.to_owned();
+    let mut message_has_parts = false;
+    for part in parts {
+        if message_has_parts && message.len() + ", ".len() + part.len() > max_len {
+            send_command(bot, bot_state, &format!("msg {receiver} {message}"));
+            message = String::new();
+            message_has_parts = false;
+        }
+        if message_has_parts {
+            message.push_str(", ");
+        }
+        message.push_str(part);
+        message_has_parts = true;
+    }
+    send_command(bot, bot_state, &format!("msg {receiver} {message}"));
+}
+
+/// Format a duration in a human readable way (e.g. "1h 2m 3s").
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 60 * 60 {
+        format!("{}h {}m {}s", secs / (60 * 60), secs / 60 % 60, secs % 60)
+    } else if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+/// Read the value of a field from /proc/self/status (e.g. "VmRSS" => "12345 kB").
+fn proc_status_field(field: &str) -> Option<String> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name == field {
+            Some(value.trim().to_owned())
+        } else {
+            None
+        }
+    })
+}
+
+pub fn send_command(bot: &mut Client, bot_state: &BotState, command: &str) {
+    if bot_state.is_quiet() {
         info!("Quiet mode: Supressed sending command: {command}");
     } else {
         info!("Sending command: {command}");
@@ -9,18 +9,28 @@ extern crate tracing;
 use anyhow::{Context, Result};
 use azalea::{
     auth::AuthResult,
-    blocks::Block,
+    blocks::{properties::Powered, Block, BlockState},
     core::direction::Direction,
     ecs::query::With,
-    entity::{metadata::Player, EyeHeight, Pose, Position},
-    inventory::{InventoryComponent, ItemSlot, SetSelectedHotbarSlotEvent},
+    entity::{
+        metadata::{EnderPearl, Player},
+        EyeHeight, LookDirection, Pose, Position,
+    },
+    inventory::{
+        CloseContainerEvent, InventoryComponent, ItemSlot, ItemSlotData, SetSelectedHotbarSlotEvent,
+    },
     packet_handling::game::SendPacketEvent,
-    pathfinder::{goals::BlockPosGoal, Pathfinder},
+    pathfinder::{
+        goals::{BlockPosGoal, Goal},
+        Pathfinder, StopPathfindingEvent,
+    },
     prelude::*,
     protocol::packets::game::{
         serverbound_interact_packet::InteractionHand,
         serverbound_player_action_packet::ServerboundPlayerActionPacket,
+        serverbound_player_input_packet::ServerboundPlayerInputPacket,
         serverbound_set_carried_item_packet::ServerboundSetCarriedItemPacket,
+        serverbound_swing_packet::ServerboundSwingPacket,
         serverbound_use_item_on_packet::{BlockHit, ServerboundUseItemOnPacket},
         serverbound_use_item_packet::ServerboundUseItemPacket,
         ClientboundGamePacket, ServerboundGamePacket,
@@ -39,7 +49,10 @@ use std::{
     fmt::Debug,
     io::IsTerminal,
     path::PathBuf,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 use tracing_subscriber::prelude::*;
@@ -73,6 +86,14 @@ struct Opts {
     #[clap(short = 'H', long)]
     autolog_hp: Option<f32>,
 
+    /// What to do, when --autolog-hp triggers. "disconnect" leaves the server and rejoins after --autolog-reconnect-secs.
+    #[clap(long, value_enum, default_value = "exit")]
+    autolog_mode: AutologMode,
+
+    /// With --autolog-mode disconnect: How long to wait before rejoining
+    #[clap(long, default_value = "300")]
+    autolog_reconnect_secs: u64,
+
     /// Workaround for crashes: Forbid the bot from sending any messages to players.
     #[clap(short = 'q', long)]
     quiet: bool,
@@ -110,14 +131,130 @@ struct Opts {
     #[clap(short = 'L', long)]
     look_at_players: Option<u32>,
 
+    /// Yaw to look towards, while idle (e.g. 0 = south, 90 = west)
+    #[clap(long, allow_hyphen_values = true)]
+    idle_yaw: Option<f32>,
+
+    /// Pitch to look towards, while idle (e.g. 90 = straight down, 0 = straight ahead)
+    #[clap(long, allow_hyphen_values = true)]
+    idle_pitch: Option<f32>,
+
+    /// Anti-AFK: After being idle for N seconds, step to an adjacent block and back
+    #[clap(long)]
+    idle_wander_secs: Option<u64>,
+
+    /// Swing the main hand every N ticks while pathfinding. Purely cosmetic.
+    #[clap(long)]
+    swing_while_pathfinding: Option<u64>,
+
+    /// Refuse !comehere, when the admin is more than N blocks away horizontally (0 = unlimited)
+    #[clap(long, default_value = "256")]
+    comehere_max_dist: f64,
+
     /// Enable a command, that allows admins to get the position of the bot. Might be dangerous!
     #[clap(long)]
     enable_pos_command: bool,
 
+    /// Walk to stasis chambers (and blocks to !flip) and back, but don't actually flip anything. For testing new setups.
+    #[clap(long)]
+    stasis_dry_run: bool,
+
+    /// Before walking to a stasis chamber, check that a pearl is still in it (only possible if it's in render distance)
+    #[clap(long)]
+    verify_pearl: bool,
+
+    /// Eat golden apples from the hotbar, when recently damaged and at or below N HP
+    #[clap(long)]
+    combat_eat: Option<f32>,
+
+    /// Refuse to pull for the same player again within this many seconds (0 = disabled)
+    #[clap(long, default_value = "0")]
+    tp_cooldown_secs: u64,
+
+    /// Refuse to pull, when the requesting player is already within N blocks of their stasis chamber
+    #[clap(long)]
+    already_here_radius: Option<f64>,
+
+    /// Pull the stasis chamber of the closest player, when the button, lever or (non-weighted) pressure plate at this position (x,y,z) gets activated
+    #[clap(long)]
+    pull_button: Option<BlockPos>,
+
+    /// Max distance of the player to the --pull-button, to be considered the one pressing it
+    #[clap(long, default_value = "4")]
+    pull_button_radius: f64,
+
     /// Enables Automatic Eating food items in hotbar, when appropriate
     #[clap(long)]
     auto_eat: bool,
 
+    /// Don't take over a stasis chamber, that is already used by another player, when someone else throws a pearl into it
+    #[clap(long)]
+    refuse_occupied_chambers: bool,
+
+    /// Say this in chat, when a player comes into render distance. "{player}" gets replaced with their name.
+    #[clap(long)]
+    greet: Option<String>,
+
+    /// Only greet these players with --greet. Greets everyone if not specified.
+    #[clap(long)]
+    greet_player: Vec<String>,
+
+    /// Don't greet the same player again for this many seconds
+    #[clap(long, default_value = "600")]
+    greet_cooldown_secs: u64,
+
+    /// Automatically close any container (chest, gui, etc.), that was left open for more than N seconds
+    #[clap(long)]
+    auto_close_container_secs: Option<u64>,
+
+    /// Periodically send this, to not get kicked for inactivity. Starting with "/" sends it as command (preferred), otherwise as chat message.
+    #[clap(long)]
+    keepalive_message: Option<String>,
+
+    /// How often to send the --keepalive-message
+    #[clap(long, default_value = "300")]
+    keepalive_interval_secs: u64,
+
+    /// Prefix, that commands sent to the bot start with. Commands without it are still understood.
+    #[clap(long, default_value = "!")]
+    command_prefix: String,
+
+    /// How long to wait after disconnecting, before quitting the process (e.g. for !stop or low health)
+    #[clap(long, default_value = "500")]
+    disconnect_wait_ms: u64,
+
+    /// Read commands and chat messages from stdin, even if it isn't a terminal (e.g. a pipe)
+    #[clap(long)]
+    read_stdin: bool,
+
+    /// Once ingame, run each line of this file like it was typed into the console. Empty lines and lines starting with # are skipped.
+    #[clap(long)]
+    exec_file: Option<PathBuf>,
+
+    /// Delay before each line of --exec-file gets run
+    #[clap(long, default_value = "1000")]
+    exec_delay_ms: u64,
+
+    /// Give up on a pull (or flip), that hasn't finished after this many seconds. Prevents getting stuck forever.
+    #[clap(long)]
+    task_deadline_secs: Option<u64>,
+
+    /// Seconds to wait before the first attempt to rejoin after getting disconnected
+    #[clap(long, default_value = "5")]
+    reconnect_initial_secs: u64,
+
+    /// Maximum seconds to wait between attempts to rejoin
+    #[clap(long, default_value = "1800")]
+    reconnect_max_secs: u64,
+
+    /// Multiply the wait by this after each failed attempt to rejoin
+    #[clap(long, default_value = "2.0")]
+    reconnect_multiplier: f64,
+
+    /// Warn, when handling a single event takes longer than this
+    #[clap(long, default_value = "50")]
+    slow_handler_warn_ms: u64,
+
     /// File, used to store authentication information in. Ignored if --offline-username is used.
     #[clap(long, default_value = "login-secrets.json")]
     auth_file: PathBuf,
@@ -127,6 +264,98 @@ struct Opts {
     just_print_access_token: bool,
 }
 
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum AutologMode {
+    /// Quit the process with EXITCODE_LOW_HEALTH_OR_TOTEM_POP
+    Exit,
+    /// Only disconnect and rejoin later
+    Disconnect,
+}
+
+impl Opts {
+    /// Effective options as "name=value" pairs. Paths are redacted.
+    fn describe(&self) -> Vec<String> {
+        fn optional<T: std::fmt::Display>(value: &Option<T>) -> String {
+            match value {
+                Some(value) => value.to_string(),
+                None => "off".to_owned(),
+            }
+        }
+        fn redacted<T>(value: &Option<T>) -> &'static str {
+            if value.is_some() {
+                "<set>"
+            } else {
+                "off"
+            }
+        }
+
+        vec![
+            format!("server_address={}", self.server_address),
+            format!("admin={}", self.admin.join(",")),
+            format!("via={}", optional(&self.via)),
+            format!("autolog_hp={}", optional(&self.autolog_hp)),
+            format!("autolog_mode={:?}", self.autolog_mode).to_lowercase(),
+            format!("autolog_reconnect_secs={}", self.autolog_reconnect_secs),
+            format!("quiet={}", self.quiet),
+            format!("no_stasis={}", self.no_stasis),
+            format!("log_file={}", redacted(&self.log_file)),
+            format!("no_color={}", self.no_color),
+            format!("sign_chat={}", self.sign_chat),
+            format!("offline_username={}", optional(&self.offline_username)),
+            format!("openauthmod={}", self.openauthmod),
+            format!("no_mining={}", self.no_mining),
+            format!("look_at_players={}", optional(&self.look_at_players)),
+            format!("idle_yaw={}", optional(&self.idle_yaw)),
+            format!("idle_pitch={}", optional(&self.idle_pitch)),
+            format!("idle_wander_secs={}", optional(&self.idle_wander_secs)),
+            format!(
+                "swing_while_pathfinding={}",
+                optional(&self.swing_while_pathfinding)
+            ),
+            format!("comehere_max_dist={}", self.comehere_max_dist),
+            format!("enable_pos_command={}", self.enable_pos_command),
+            format!("stasis_dry_run={}", self.stasis_dry_run),
+            format!("verify_pearl={}", self.verify_pearl),
+            format!("combat_eat={}", optional(&self.combat_eat)),
+            format!("tp_cooldown_secs={}", self.tp_cooldown_secs),
+            format!(
+                "already_here_radius={}",
+                optional(&self.already_here_radius)
+            ),
+            format!(
+                "pull_button={}",
+                optional(
+                    &self
+                        .pull_button
+                        .map(|pos| format!("{},{},{}", pos.x, pos.y, pos.z))
+                )
+            ),
+            format!("pull_button_radius={}", self.pull_button_radius),
+            format!("auto_eat={}", self.auto_eat),
+            format!("refuse_occupied_chambers={}", self.refuse_occupied_chambers),
+            format!("greet={}", optional(&self.greet)),
+            format!("greet_player={}", self.greet_player.join(",")),
+            format!("greet_cooldown_secs={}", self.greet_cooldown_secs),
+            format!(
+                "auto_close_container_secs={}",
+                optional(&self.auto_close_container_secs)
+            ),
+            format!("keepalive_message={}", optional(&self.keepalive_message)),
+            format!("keepalive_interval_secs={}", self.keepalive_interval_secs),
+            format!("command_prefix={}", self.command_prefix),
+            format!("disconnect_wait_ms={}", self.disconnect_wait_ms),
+            format!("read_stdin={}", self.read_stdin),
+            format!("exec_file={}", redacted(&self.exec_file)),
+            format!("exec_delay_ms={}", self.exec_delay_ms),
+            format!("task_deadline_secs={}", optional(&self.task_deadline_secs)),
+            format!("reconnect_initial_secs={}", self.reconnect_initial_secs),
+            format!("reconnect_max_secs={}", self.reconnect_max_secs),
+            format!("reconnect_multiplier={}", self.reconnect_multiplier),
+            format!("slow_handler_warn_ms={}", self.slow_handler_warn_ms),
+        ]
+    }
+}
+
 pub const FOOD_ITEMS: &[Item] = &[
     Item::Apple,
     Item::GoldenApple,
@@ -163,8 +392,55 @@ pub const FOOD_ITEMS: &[Item] = &[
     Item::RabbitStew,
 ];
 
+/// Getting moved back by the server this often within the window, is considered rubber-banding
+const RUBBER_BANDING_MIN_CORRECTIONS: usize = 3;
+const RUBBER_BANDING_WINDOW: Duration = Duration::from_secs(5);
+
+/// Players appearing this soon after joining (or changing dimension) were already there and don't get greeted
+const GREET_IGNORE_AFTER_JOIN: Duration = Duration::from_secs(5);
+/// Minimum time between any two greetings, to not get kicked for spamming
+const GREET_MIN_GAP: Duration = Duration::from_secs(3);
+
 static OPTS: Lazy<Opts> = Lazy::new(|| Opts::parse());
 static INPUTLINE_QUEUE: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static PULL_STATS_SAVE_LOCK: Mutex<()> = Mutex::new(());
+static EXEC_FILE_STARTED: AtomicBool = AtomicBool::new(false);
+/// Set when the bot is about to exit on purpose, so it doesn't rejoin in the meantime
+static QUITTING: AtomicBool = AtomicBool::new(false);
+static RECONNECT_STATS: Mutex<ReconnectStats> = Mutex::new(ReconnectStats {
+    consecutive_failures: 0,
+    total_reconnects: 0,
+});
+
+/// Last error returned while handling each kind of event (see !errors)
+static LAST_ERRORS: Lazy<Mutex<HashMap<&'static str, (String, Instant)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+/// Unix timestamp, at which the certificates used for chat signing expire
+static CHAT_CERTS_EXPIRE_AT: Mutex<Option<i64>> = Mutex::new(None);
+/// Don't rejoin before this time (e.g. after --autolog-mode disconnect)
+static RECONNECT_NOT_BEFORE: Mutex<Option<Instant>> = Mutex::new(None);
+/// Initialized from --reconnect-* options. Can be changed at runtime.
+static RECONNECT_CONFIG: Mutex<ReconnectConfig> = Mutex::new(ReconnectConfig {
+    initial_delay_secs: 5,
+    max_delay_secs: 60 * 30,
+    multiplier: 2.0,
+});
+
+pub struct ReconnectConfig {
+    /// Delay before the first attempt to rejoin
+    initial_delay_secs: u64,
+    /// The delay grows after each failed attempt, up to this
+    max_delay_secs: u64,
+    /// Factor, by which the delay grows after each failed attempt
+    multiplier: f64,
+}
+
+pub struct ReconnectStats {
+    /// Failed attempts to rejoin since the last successful one
+    consecutive_failures: u32,
+    /// Successful rejoins since starting (or last reset)
+    total_reconnects: u32,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 struct BlockPos {
@@ -173,6 +449,23 @@ struct BlockPos {
     z: i32,
 }
 
+impl std::str::FromStr for BlockPos {
+    type Err = String;
+
+    /// Parse a position in the form "x,y,z"
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let coords = s
+            .split(',')
+            .map(|coord| coord.trim().parse::<i32>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|err| format!("Invalid coordinate: {err}"))?;
+        match coords[..] {
+            [x, y, z] => Ok(Self { x, y, z }),
+            _ => Err("Expected a position like x,y,z".to_owned()),
+        }
+    }
+}
+
 impl From<azalea::BlockPos> for BlockPos {
     fn from(value: azalea::BlockPos) -> Self {
         Self {
@@ -230,6 +523,22 @@ async fn main() -> Result<()> {
         std::process::exit(EXITCODE_CONFLICTING_CLI_OPTS);
     }
 
+    if OPTS.reconnect_initial_secs == 0 || OPTS.reconnect_initial_secs > OPTS.reconnect_max_secs {
+        error!(
+            "--reconnect-initial-secs must be at least 1 and not more than --reconnect-max-secs!"
+        );
+        std::process::exit(EXITCODE_CONFLICTING_CLI_OPTS);
+    }
+    if OPTS.reconnect_multiplier.is_nan() || OPTS.reconnect_multiplier < 1.0 {
+        error!("--reconnect-multiplier must be at least 1.0!");
+        std::process::exit(EXITCODE_CONFLICTING_CLI_OPTS);
+    }
+    *RECONNECT_CONFIG.lock() = ReconnectConfig {
+        initial_delay_secs: OPTS.reconnect_initial_secs,
+        max_delay_secs: OPTS.reconnect_max_secs,
+        multiplier: OPTS.reconnect_multiplier,
+    };
+
     if !OPTS.just_print_access_token {
         if OPTS.offline_username.is_none() {
             info!(
@@ -247,13 +556,20 @@ async fn main() -> Result<()> {
         }
 
         if let Some(autolog_hp) = OPTS.autolog_hp {
-            info!("Will automatically logout and quit, when getting to or below {autolog_hp} HP or popping a totem.");
+            match OPTS.autolog_mode {
+                AutologMode::Exit => info!("Will automatically logout and quit, when getting to or below {autolog_hp} HP or popping a totem."),
+                AutologMode::Disconnect => info!("Will automatically logout and rejoin after {} seconds, when getting to or below {autolog_hp} HP or popping a totem.", OPTS.autolog_reconnect_secs),
+            }
         }
 
         if OPTS.no_stasis {
             info!("Will not perform any stasis duties!");
         }
 
+        if OPTS.stasis_dry_run {
+            info!("Dry run: Will walk to stasis chambers, but not pull any pearls!");
+        }
+
         if OPTS.enable_pos_command {
             info!("The command !pos has been enabled for admins!");
         }
@@ -262,6 +578,43 @@ async fn main() -> Result<()> {
             info!("Automatic Eating is enabled.");
         }
 
+        if OPTS.refuse_occupied_chambers {
+            info!("Will not let players take over stasis chambers of other players.");
+        }
+
+        if let Some(secs) = OPTS.auto_close_container_secs {
+            info!(
+                "Will automatically close containers, that stay open for more than {secs} seconds."
+            );
+        }
+
+        if let Some(hp) = OPTS.combat_eat {
+            info!("Will eat golden apples, when getting hurt while at or below {hp} HP.");
+        }
+
+        if let Some(message) = &OPTS.keepalive_message {
+            info!(
+                "Will send {message:?} every {} seconds to stay active.",
+                OPTS.keepalive_interval_secs
+            );
+        }
+
+        if let Some(secs) = OPTS.idle_wander_secs {
+            info!("Will step to an adjacent block and back, after being idle for {secs} seconds.");
+        }
+
+        if let Some(secs) = OPTS.task_deadline_secs {
+            info!("Will give up on pulls, that take longer than {secs} seconds.");
+        }
+
+        if let Some(pos) = OPTS.pull_button {
+            info!("Will pull for the closest player, when the button at {pos:?} gets activated.");
+        }
+
+        if let Some(radius) = OPTS.already_here_radius {
+            info!("Will not pull for players, who are already within {radius} blocks of their stasis chamber.");
+        }
+
         info!("Admins: {}", OPTS.admin.join(", "));
         info!("Logging in...");
     }
@@ -309,6 +662,9 @@ async fn main() -> Result<()> {
             .request_certs()
             .await
             .context("Request certs for chat signing")?;
+        if let Some(certs) = &account.certs {
+            *CHAT_CERTS_EXPIRE_AT.lock() = Some(certs.expires_at.timestamp());
+        }
         info!("Chat signing is enabled. Retreived certs for it.");
     }
 
@@ -318,12 +674,19 @@ async fn main() -> Result<()> {
     );
 
     // Read input and put in queue
-    if std::io::stdin().is_terminal() {
+    if OPTS.read_stdin || std::io::stdin().is_terminal() {
         std::thread::spawn(|| loop {
             let mut line = String::new();
-            if let Err(err) = std::io::stdin().read_line(&mut line) {
-                warn!("Not accepting any input anymore because reading failed: Err: {err}");
-                return;
+            match std::io::stdin().read_line(&mut line) {
+                Ok(0) => {
+                    info!("Reached end of input. Not accepting any input anymore.");
+                    return;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    warn!("Not accepting any input anymore because reading failed: Err: {err}");
+                    return;
+                }
             }
             let line: &str = line.trim();
 
@@ -351,6 +714,29 @@ async fn main() -> Result<()> {
         .context("Running bot")?
 }
 
+/// Put each line of the given file into the input queue, as if typed into the console.
+async fn execute_file(path: PathBuf) {
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(err) => {
+            error!("Failed to read file {path:?} for executing it: {err}");
+            return;
+        }
+    };
+
+    info!("Executing lines from {path:?}...");
+    for line in content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        tokio::time::sleep(Duration::from_millis(OPTS.exec_delay_ms)).await;
+        info!("Executing line from {path:?}: {line}");
+        INPUTLINE_QUEUE.lock().push_back(line.to_owned());
+    }
+    info!("Finished executing lines from {path:?}.");
+}
+
 async fn auth() -> Result<AuthResult> {
     Ok(azalea::auth::auth(
         "default",
@@ -362,25 +748,97 @@ async fn auth() -> Result<AuthResult> {
     .await?)
 }
 
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PullStats {
+    /// Walked to a stasis chamber to pull it
+    started: u64,
+    /// Arrived and flipped the trapdoor
+    succeeded: u64,
+    /// Refused, because already going somewhere else
+    refused_busy: u64,
+    /// Refused, because no pearl of that player was known
+    refused_unknown: u64,
+    /// Refused for other reasons (suspended, cooldown, already there, no pearl visible)
+    refused_other: u64,
+    /// Started, but given up on after --task-deadline-secs
+    cancelled: u64,
+}
+
 #[derive(Default, Clone, Component)]
 pub struct BotState {
     remembered_trapdoor_positions: Arc<Mutex<HashMap<String, BlockPos>>>,
     pathfinding_requested_by: Arc<Mutex<Option<String>>>,
+    /// When pathfinding_requested_by was last set
+    pathfinding_requested_at: Arc<Mutex<Option<Instant>>>,
     return_to_after_pulled: Arc<Mutex<Option<azalea::Vec3>>>,
     last_dm_handled_at: Arc<Mutex<Option<Instant>>>,
     eating_until_nutrition_over: Arc<Mutex<Option<u32>>>,
+    server_view_distance: Arc<Mutex<Option<u32>>>,
+    server_simulation_distance: Arc<Mutex<Option<u32>>>,
+    quiet_override: Arc<Mutex<Option<bool>>>,
+    pending_flip: Arc<Mutex<Option<BlockPos>>>,
+    container_open_since: Arc<Mutex<Option<Instant>>>,
+    last_pathfinding_swing: Arc<Mutex<Option<Instant>>>,
+    eating_started_at: Arc<Mutex<Option<Instant>>>,
+    last_health: Arc<Mutex<Option<f32>>>,
+    last_damaged_at: Arc<Mutex<Option<Instant>>>,
+    pull_stats: Arc<Mutex<PullStats>>,
+    position_corrections: Arc<Mutex<VecDeque<Instant>>>,
+    last_greeted: Arc<Mutex<HashMap<String, Instant>>>,
+    last_greeting_sent: Arc<Mutex<Option<Instant>>>,
+    /// When we last logged in or changed dimension
+    joined_world_at: Arc<Mutex<Option<Instant>>>,
+    last_pearl_throw: Arc<Mutex<Option<(String, Instant)>>>,
+    /// Lowercase player names, whose pulls get ignored until the given time
+    suspended_until: Arc<Mutex<HashMap<String, Instant>>>,
+    /// Lowercase player names and when a pull was last started for them (for --tp-cooldown-secs)
+    last_pull_started: Arc<Mutex<HashMap<String, Instant>>>,
+    /// Last known state of --pull-button
+    pull_button_powered: Arc<Mutex<bool>>,
+    last_keepalive: Arc<Mutex<Option<Instant>>>,
+    idle_since: Arc<Mutex<Option<Instant>>>,
+    /// Where to step back to, after wandering off for --idle-wander-secs
+    wander_home: Arc<Mutex<Option<BlockPos>>>,
+    /// Entity id of the vehicle (boat, minecart, ...), that we're sitting in
+    riding_vehicle: Arc<Mutex<Option<u32>>>,
+    /// Id of the ping request sent for !ping, who asked and when it was sent
+    pending_ping: Arc<Mutex<Option<(u64, String, Instant)>>>,
 }
 
 impl BotState {
+    /// Whether sending messages is forbidden. Can be toggled at runtime, but defaults to --quiet.
+    pub fn is_quiet(&self) -> bool {
+        self.quiet_override.lock().unwrap_or(OPTS.quiet)
+    }
+
+    /// Whether the server recently kept moving us back (likely anticheat or failed movement).
+    pub fn is_rubber_banding(&self) -> bool {
+        let position_corrections = self.position_corrections.lock();
+        position_corrections
+            .iter()
+            .filter(|at| at.elapsed() <= RUBBER_BANDING_WINDOW)
+            .count()
+            >= RUBBER_BANDING_MIN_CORRECTIONS
+            && position_corrections
+                .back()
+                .is_some_and(|at| at.elapsed() < Duration::from_secs(1))
+    }
+
     pub fn remembered_trapdoor_positions_path() -> PathBuf {
         PathBuf::from("remembered-trapdoor-positions.json")
     }
 
     pub async fn load_stasis(&mut self) -> Result<()> {
         let remembered_trapdoor_positions_path = Self::remembered_trapdoor_positions_path();
-        if remembered_trapdoor_positions_path.exists()
-            && !remembered_trapdoor_positions_path.is_dir()
-        {
+        if remembered_trapdoor_positions_path.is_dir() {
+            // E.g. a botched docker volume mount. Saving would fail every time.
+            error!(
+                "{remembered_trapdoor_positions_path:?} is a directory, but needs to be a file! Quitting..."
+            );
+            std::process::exit(EXITCODE_CONFLICTING_CLI_OPTS);
+        }
+        if remembered_trapdoor_positions_path.exists() {
             *self.remembered_trapdoor_positions.lock() = serde_json::from_str(
                 &tokio::fs::read_to_string(remembered_trapdoor_positions_path)
                     .await
@@ -396,9 +854,60 @@ impl BotState {
             warn!("File for rememembered trapdoor positions doesn't exist, yet.");
         };
 
+        let pull_stats_path = Self::pull_stats_path();
+        if pull_stats_path.is_dir() {
+            error!("{pull_stats_path:?} is a directory, but needs to be a file! Quitting...");
+            std::process::exit(EXITCODE_CONFLICTING_CLI_OPTS);
+        }
+        if pull_stats_path.exists() {
+            // Only statistics, so not worth failing over
+            let pull_stats = tokio::fs::read_to_string(&pull_stats_path)
+                .await
+                .context("Read pull_stats file")
+                .and_then(|content| {
+                    serde_json::from_str(&content).context("Parsing pull_stats content")
+                });
+            match pull_stats {
+                Ok(pull_stats) => *self.pull_stats.lock() = pull_stats,
+                Err(err) => {
+                    warn!("Failed to load pull stats from {pull_stats_path:?}. Starting from zero: {err:?}");
+                    *self.pull_stats.lock() = Default::default();
+                }
+            }
+        }
+
         Ok(())
     }
 
+    pub fn pull_stats_path() -> PathBuf {
+        PathBuf::from("pull-stats.json")
+    }
+
+    /// Save the current pull stats. Blocking. Saves never overlap and replace the file at once,
+    /// so it can't end up with half-written content.
+    pub fn save_pull_stats(&self) -> Result<()> {
+        let _saving = PULL_STATS_SAVE_LOCK.lock();
+        let json = serde_json::to_string_pretty(&*self.pull_stats.as_ref().lock())
+            .context("Convert pull_stats to json")?;
+        let pull_stats_path = Self::pull_stats_path();
+        let temp_path = pull_stats_path.with_extension("json.tmp");
+        std::fs::write(&temp_path, json).context("Save pull_stats as temporary file")?;
+        std::fs::rename(&temp_path, &pull_stats_path)
+            .context("Replace pull_stats file with temporary file")?;
+        Ok(())
+    }
+
+    /// Update the pull statistics and save them in the background.
+    pub fn record_pull_stat(&self, update: impl FnOnce(&mut PullStats)) {
+        update(&mut self.pull_stats.lock());
+        let bot_state = self.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Err(err) = bot_state.save_pull_stats() {
+                error!("Failed to save pull stats to file: {err:?}");
+            }
+        });
+    }
+
     pub async fn save_stasis(&self) -> Result<()> {
         let json =
             serde_json::to_string_pretty(&*self.remembered_trapdoor_positions.as_ref().lock())
@@ -410,13 +919,52 @@ impl BotState {
     }
 }
 
-async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyhow::Result<()> {
+async fn handle(bot: Client, event: Event, bot_state: BotState) -> anyhow::Result<()> {
+    let event_name = match &event {
+        Event::Login => "Login",
+        Event::Chat(_) => "Chat",
+        Event::Packet(_) => "Packet",
+        Event::Tick => "Tick",
+        _ => "Other",
+    };
+    let started_at = Instant::now();
+    let result = handle_event(bot, event, bot_state).await;
+    let took = started_at.elapsed();
+    if took > Duration::from_millis(OPTS.slow_handler_warn_ms) {
+        warn!(
+            "Handling event {event_name} took {}ms. This might stall ticks!",
+            took.as_millis()
+        );
+    }
+    if let Err(err) = &result {
+        // Keep it short enough to fit into a chat message
+        let message = err.to_string().chars().take(100).collect::<String>();
+        LAST_ERRORS
+            .lock()
+            .insert(event_name, (message, Instant::now()));
+    }
+    result
+}
+
+async fn handle_event(
+    mut bot: Client,
+    event: Event,
+    mut bot_state: BotState,
+) -> anyhow::Result<()> {
     match event {
         Event::Login => {
+            bot_state.last_pull_started.lock().clear();
             if !OPTS.no_stasis {
                 info!("Loading remembered trapdoor positions...");
                 bot_state.load_stasis().await?;
             }
+
+            // Only run once, not again after reconnecting
+            if let Some(exec_file) = &OPTS.exec_file
+                && !EXEC_FILE_STARTED.swap(true, Ordering::SeqCst)
+            {
+                tokio::spawn(execute_file(exec_file.clone()));
+            }
         }
         Event::Chat(packet) => {
             info!(
@@ -453,26 +1001,101 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                     (content, vec![])
                 };
 
-                if bot_state
-                    .last_dm_handled_at
-                    .lock()
-                    .map(|at| at.elapsed() > Duration::from_secs(1))
-                    .unwrap_or(true)
-                {
-                    info!("Executing command {command:?} sent by {sender:?} with args {args:?}");
-                    if commands::execute(&mut bot, &bot_state, sender, command, args)
-                        .context("Executing command")?
-                    {
-                        *bot_state.last_dm_handled_at.lock() = Some(Instant::now());
-                    } else {
-                        warn!("Command was not executed. Most likely an unknown command.");
-                    }
-                } else {
-                    warn!("Last command was handled less than a second ago. Ignoring command from {sender:?} to avoid getting spam kicked.");
-                }
+                execute_rate_limited(&mut bot, &bot_state, sender, command, args)?;
             }
         }
         Event::Packet(packet) => match packet.as_ref() {
+            ClientboundGamePacket::Login(packet) => {
+                *bot_state.joined_world_at.lock() = Some(Instant::now());
+                *bot_state.server_view_distance.lock() = Some(packet.chunk_radius);
+                *bot_state.server_simulation_distance.lock() = Some(packet.simulation_distance);
+            }
+            ClientboundGamePacket::PlayerPosition(_) => {
+                let mut position_corrections = bot_state.position_corrections.lock();
+                position_corrections.push_back(Instant::now());
+                while position_corrections
+                    .front()
+                    .is_some_and(|at| at.elapsed() > RUBBER_BANDING_WINDOW)
+                {
+                    position_corrections.pop_front();
+                }
+                if position_corrections.len() >= RUBBER_BANDING_MIN_CORRECTIONS {
+                    warn!(
+                        "The server moved me back {} times in the last {} seconds. Getting rubber-banded?",
+                        position_corrections.len(),
+                        RUBBER_BANDING_WINDOW.as_secs()
+                    );
+                }
+            }
+            ClientboundGamePacket::BlockUpdate(packet) => {
+                handle_pull_button_update(
+                    &mut bot,
+                    &bot_state,
+                    BlockPos::from(packet.pos),
+                    packet.block_state,
+                )?;
+            }
+            ClientboundGamePacket::SectionBlocksUpdate(packet) => {
+                // Multiple changes in the same chunk section (e.g. button and powered redstone) get batched
+                for state_with_pos in &packet.states {
+                    let block_pos = BlockPos {
+                        x: packet.section_pos.x * 16 + state_with_pos.pos.x as i32,
+                        y: packet.section_pos.y * 16 + state_with_pos.pos.y as i32,
+                        z: packet.section_pos.z * 16 + state_with_pos.pos.z as i32,
+                    };
+                    handle_pull_button_update(
+                        &mut bot,
+                        &bot_state,
+                        block_pos,
+                        state_with_pos.state,
+                    )?;
+                }
+            }
+            ClientboundGamePacket::LevelChunkWithLight(packet) => {
+                // Don't carry over the state from before the chunk got (re)loaded
+                if let Some(button_pos) = OPTS.pull_button
+                    && button_pos.x.div_euclid(16) == packet.x
+                    && button_pos.z.div_euclid(16) == packet.z
+                {
+                    *bot_state.pull_button_powered.lock() = false;
+                }
+            }
+            ClientboundGamePacket::PongResponse(packet) => {
+                let pending_ping = bot_state
+                    .pending_ping
+                    .lock()
+                    .take_if(|(id, _, _)| *id == packet.time);
+                if let Some((_, requested_by, sent_at)) = pending_ping {
+                    commands::send_command(
+                        &mut bot,
+                        &bot_state,
+                        &format!(
+                            "msg {requested_by} Ping: {}ms",
+                            sent_at.elapsed().as_millis()
+                        ),
+                    );
+                }
+            }
+            ClientboundGamePacket::SetPassengers(packet) => {
+                let my_entity_id = bot.entity_component::<MinecraftEntityId>(bot.entity).0;
+                let mut riding_vehicle = bot_state.riding_vehicle.lock();
+                if packet.passengers.contains(&my_entity_id) {
+                    info!("I'm now riding the vehicle {}.", packet.vehicle);
+                    *riding_vehicle = Some(packet.vehicle);
+                } else if *riding_vehicle == Some(packet.vehicle) {
+                    info!("I'm no longer riding the vehicle {}.", packet.vehicle);
+                    *riding_vehicle = None;
+                }
+            }
+            ClientboundGamePacket::Respawn(_) => {
+                *bot_state.joined_world_at.lock() = Some(Instant::now());
+            }
+            ClientboundGamePacket::SetChunkCacheRadius(packet) => {
+                *bot_state.server_view_distance.lock() = Some(packet.radius);
+            }
+            ClientboundGamePacket::SetSimulationDistance(packet) => {
+                *bot_state.server_simulation_distance.lock() = Some(packet.simulation_distance);
+            }
             ClientboundGamePacket::AddEntity(packet) => {
                 if !OPTS.no_stasis && packet.entity_type == EntityKind::EnderPearl {
                     let owning_player_entity_id = packet.data;
@@ -482,7 +1105,10 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                             entity_id.0 as i32 == owning_player_entity_id
                         },
                     );
-                    if let Some(entity) = entity {
+                    let my_entity_id = bot.entity_component::<MinecraftEntityId>(bot.entity).0;
+                    if my_entity_id as i32 == owning_player_entity_id {
+                        info!("Ignoring EnderPearl thrown by myself.");
+                    } else if let Some(entity) = entity {
                         let game_profile = bot.entity_component::<GameProfileComponent>(entity);
                         info!(
                             "{} threw an EnderPearl at {}",
@@ -516,7 +1142,33 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                             }
                         }
 
-                        if let Some(block_pos) = found_trapdoor {
+                        let occupied_by = found_trapdoor.and_then(|block_pos| {
+                            bot_state
+                                .remembered_trapdoor_positions
+                                .lock()
+                                .iter()
+                                .find(|(playername, pos)| {
+                                    **pos == block_pos && **playername != game_profile.name
+                                })
+                                .map(|(playername, _)| playername.to_owned())
+                        });
+
+                        if OPTS.refuse_occupied_chambers
+                            && let Some(occupied_by) = occupied_by
+                        {
+                            warn!(
+                                "{} threw a pearl into the stasis chamber of {occupied_by}. Not remembering it!",
+                                game_profile.name
+                            );
+                            if !bot_state.is_quiet() {
+                                bot.send_command_packet(&format!(
+                                    "msg {} That chamber belongs to someone else.",
+                                    game_profile.name
+                                ));
+                            }
+                        } else if let Some(block_pos) = found_trapdoor {
+                            *bot_state.last_pearl_throw.lock() =
+                                Some((game_profile.name.clone(), Instant::now()));
                             if bot_state
                                 .remembered_trapdoor_positions
                                 .lock()
@@ -541,7 +1193,7 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                                 remembered_trapdoor_positions
                                     .insert(game_profile.name.clone(), block_pos);
 
-                                if !OPTS.quiet {
+                                if !bot_state.is_quiet() {
                                     bot.send_command_packet(&format!("msg {} You have thrown a pearl. Message me \"tp\" to get back here.", game_profile.name));
                                 }
                                 let bot_state = bot_state.clone();
@@ -562,6 +1214,39 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                         "A player appeared at {} with entity id {}",
                         packet.position, packet.id
                     );*/
+                    if let Some(greeting) = &OPTS.greet
+                        && let Some(player_info) = bot.tab_list().get(&packet.uuid)
+                    {
+                        let name = &player_info.profile.name;
+                        let is_greeted_player = OPTS.greet_player.is_empty()
+                            || OPTS
+                                .greet_player
+                                .iter()
+                                .any(|player| player.eq_ignore_ascii_case(name));
+                        let mut last_greeted = bot_state.last_greeted.lock();
+                        let cooled_down = last_greeted
+                            .get(name)
+                            .map(|at| at.elapsed() > Duration::from_secs(OPTS.greet_cooldown_secs))
+                            .unwrap_or(true);
+                        let just_joined = bot_state
+                            .joined_world_at
+                            .lock()
+                            .is_some_and(|at| at.elapsed() < GREET_IGNORE_AFTER_JOIN);
+                        let mut last_greeting_sent = bot_state.last_greeting_sent.lock();
+                        let greeted_recently =
+                            last_greeting_sent.is_some_and(|at| at.elapsed() < GREET_MIN_GAP);
+                        if is_greeted_player && cooled_down && !just_joined && !greeted_recently {
+                            last_greeted.insert(name.to_owned(), Instant::now());
+                            *last_greeting_sent = Some(Instant::now());
+                            let message = greeting.replace("{player}", name);
+                            if bot_state.is_quiet() {
+                                info!("Quiet mode: Supressed greeting {name}: {message}");
+                            } else {
+                                info!("Greeting {name}: {message}");
+                                bot.send_chat_packet(&message);
+                            }
+                        }
+                    }
                 }
             }
             ClientboundGamePacket::EntityEvent(packet) => {
@@ -570,9 +1255,9 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                     // Totem popped!
                     info!("I popped a Totem!");
                     if OPTS.autolog_hp.is_some() {
-                        warn!("Disconnecting and quitting because --autolog-hp is enabled...");
-                        bot.disconnect();
-                        std::process::exit(EXITCODE_LOW_HEALTH_OR_TOTEM_POP);
+                        warn!("Autologging because --autolog-hp is enabled...");
+                        autolog(&mut bot);
+                        return Ok(());
                     }
                 }
             }
@@ -583,12 +1268,23 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                 );
                 if let Some(hp) = OPTS.autolog_hp {
                     if packet.health <= hp {
-                        warn!("My Health got below {hp:.02}! Disconnecting and quitting...");
-                        bot.disconnect();
-                        std::process::exit(EXITCODE_LOW_HEALTH_OR_TOTEM_POP);
+                        warn!("My Health got below {hp:.02}! Autologging...");
+                        autolog(&mut bot);
+                        return Ok(());
                     }
                 }
 
+                // Remember when last damaged (for --combat-eat)
+                {
+                    let mut last_health = bot_state.last_health.lock();
+                    if let Some(last_health) = *last_health
+                        && packet.health < last_health
+                    {
+                        *bot_state.last_damaged_at.lock() = Some(Instant::now());
+                    }
+                    *last_health = Some(packet.health);
+                }
+
                 // TODO: Use Attribute::GenericMaxHealth instead of hardcoded 20
                 let eat_until_nutrition_over = bot_state
                     .eating_until_nutrition_over
@@ -597,8 +1293,16 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                     .clone();
                 if let Some(eat_until_nutrition_over) = eat_until_nutrition_over {
                     // Is eating
-                    if packet.food > eat_until_nutrition_over {
-                        // Food increased, stop eating
+                    let eating_for = bot_state
+                        .eating_started_at
+                        .lock()
+                        .map(|at| at.elapsed())
+                        .unwrap_or_default();
+                    // Food won't increase when eating golden apples on a full stomach,
+                    // so also stop once eating must have finished (32 ticks).
+                    if packet.food > eat_until_nutrition_over || eating_for > Duration::from_secs(2)
+                    {
+                        // Food increased (or done eating), stop eating
                         bot.ecs.lock().send_event(SendPacketEvent {
                             entity: bot.entity,
                             packet: ServerboundGamePacket::PlayerAction(ServerboundPlayerActionPacket {
@@ -609,35 +1313,30 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                             })
                         });
                         *bot_state.eating_until_nutrition_over.lock() = None;
+                        *bot_state.eating_started_at.lock() = None;
                         info!("Finished eating.");
                     }
                 }
 
-                if OPTS.auto_eat
-                    && eat_until_nutrition_over.is_none()
-                    && (packet.food <= 20 - (3 * 2) || (packet.health < 20f32 && packet.food < 20))
-                {
+                let wants_food = OPTS.auto_eat
+                    && (packet.food <= 20 - (3 * 2) || (packet.health < 20f32 && packet.food < 20));
+                // Damaged within the last 10 seconds and low on health
+                let in_combat = OPTS.combat_eat.is_some_and(|hp| packet.health <= hp)
+                    && bot_state
+                        .last_damaged_at
+                        .lock()
+                        .is_some_and(|at| at.elapsed() < Duration::from_secs(10));
+                if eat_until_nutrition_over.is_none() && (wants_food || in_combat) {
                     let mut eat_item = None;
-
-                    // Find first food item in hotbar
-                    let inv = bot.entity_component::<InventoryComponent>(bot.entity);
-                    let inv_menu = inv.inventory_menu;
-                    for (hotbar_slot, slot) in inv_menu.hotbar_slots_range().enumerate() {
-                        let item = inv_menu.slot(slot);
-                        if let Some(ItemSlot::Present(item_slot)) = item {
-                            if item_slot
-                                .components
-                                .get(azalea::registry::DataComponentKind::Food)
-                                .is_some()
-                                || FOOD_ITEMS.contains(&item_slot.kind)
-                            {
-                                eat_item = Some((
-                                    hotbar_slot as u8,
-                                    format!("{} ({}x)", item_slot.kind, item_slot.count),
-                                ));
-                                break;
-                            }
-                        }
+                    if in_combat {
+                        // Prefer normal golden apples, to not waste enchanted ones
+                        eat_item = find_in_hotbar(&bot, |item| item.kind == Item::GoldenApple)
+                            .or_else(|| {
+                                find_in_hotbar(&bot, |item| item.kind == Item::EnchantedGoldenApple)
+                            });
+                    }
+                    if eat_item.is_none() && wants_food {
+                        eat_item = find_best_food_in_hotbar(&bot);
                     }
 
                     if let Some((eat_hotbar_slot, eat_item_name)) = eat_item {
@@ -667,6 +1366,7 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                             },
                         ]);
                         *bot_state.eating_until_nutrition_over.lock() = Some(packet.food);
+                        *bot_state.eating_started_at.lock() = Some(Instant::now());
                         info!("Eating {eat_item_name} in hotbar slot {eat_hotbar_slot}...");
                     }
                 }
@@ -688,18 +1388,83 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                 }
             }
 
+            // Avoid getting kicked for inactivity
+            if let Some(message) = &OPTS.keepalive_message {
+                let mut last_keepalive = bot_state.last_keepalive.lock();
+                // Don't send right after joining
+                let last_keepalive = last_keepalive.get_or_insert_with(Instant::now);
+                if last_keepalive.elapsed() >= Duration::from_secs(OPTS.keepalive_interval_secs) {
+                    *last_keepalive = Instant::now();
+                    if let Some(command) = message.strip_prefix('/') {
+                        info!("Sending keepalive command: {message}");
+                        bot.send_command_packet(command);
+                    } else if bot_state.is_quiet() {
+                        info!("Quiet mode: Supressed keepalive chat message: {message}");
+                    } else {
+                        info!("Sending keepalive chat message: {message}");
+                        bot.send_chat_packet(message);
+                    }
+                }
+            }
+
+            // Give up on !ping, if the server never answered
+            let timed_out_ping = bot_state
+                .pending_ping
+                .lock()
+                .take_if(|(_, _, sent_at)| sent_at.elapsed() > Duration::from_secs(5));
+            if let Some((_, requested_by, _)) = timed_out_ping {
+                commands::send_command(
+                    &mut bot,
+                    &bot_state,
+                    &format!(
+                        "msg {requested_by} The server didn't answer my ping within 5 seconds."
+                    ),
+                );
+            }
+
+            // Close containers, that were left open for too long
+            if let Some(secs) = OPTS.auto_close_container_secs {
+                let container_id = bot.entity_component::<InventoryComponent>(bot.entity).id;
+                let mut container_open_since = bot_state.container_open_since.lock();
+                if container_id == 0 {
+                    *container_open_since = None;
+                } else if container_open_since
+                    .get_or_insert_with(Instant::now)
+                    .elapsed()
+                    > Duration::from_secs(secs)
+                {
+                    warn!("Container {container_id} was open for more than {secs} seconds. Closing it...");
+                    bot.ecs.lock().send_event(CloseContainerEvent {
+                        entity: bot.entity,
+                        id: container_id,
+                    });
+                    *container_open_since = None;
+                }
+            }
+
+            // Swing arm while walking around
+            if let Some(interval_ticks) = OPTS.swing_while_pathfinding
+                && is_pathfinding(&bot)
+            {
+                let mut last_pathfinding_swing = bot_state.last_pathfinding_swing.lock();
+                if last_pathfinding_swing
+                    .map(|at| at.elapsed() >= Duration::from_millis(interval_ticks * 50))
+                    .unwrap_or(true)
+                {
+                    bot.ecs.lock().send_event(SendPacketEvent {
+                        entity: bot.entity,
+                        packet: ServerboundGamePacket::Swing(ServerboundSwingPacket {
+                            hand: InteractionHand::MainHand,
+                        }),
+                    });
+                    *last_pathfinding_swing = Some(Instant::now());
+                }
+            }
+
             // Look at players
+            let mut looking_at_player = false;
             if let Some(max_dist) = OPTS.look_at_players {
-                let is_pathfinding = {
-                    let mut ecs = bot.ecs.lock();
-                    let pathfinder: &Pathfinder = ecs
-                        .query::<&Pathfinder>()
-                        .get_mut(&mut *ecs, bot.entity)
-                        .unwrap();
-                    pathfinder.goal.is_some()
-                };
-
-                if !is_pathfinding {
+                if !is_pathfinding(&bot) {
                     let my_entity_id = bot.entity_component::<MinecraftEntityId>(bot.entity).0;
                     let my_pos = bot.entity_component::<Position>(bot.entity);
                     let my_eye_height = *bot.entity_component::<EyeHeight>(bot.entity) as f64;
@@ -734,11 +1499,84 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
 
                     if let Some(eye_pos) = closest_eye_pos {
                         bot.look_at(eye_pos);
+                        looking_at_player = true;
+                    }
+                }
+            }
+
+            // Rest in a neutral pose, when nothing else is going on
+            if !looking_at_player
+                && (OPTS.idle_yaw.is_some() || OPTS.idle_pitch.is_some())
+                && bot_state.pathfinding_requested_by.lock().is_none()
+                && !is_pathfinding(&bot)
+            {
+                let direction = bot.component::<LookDirection>();
+                bot.set_direction(
+                    OPTS.idle_yaw.unwrap_or(direction.y_rot),
+                    OPTS.idle_pitch.unwrap_or(direction.x_rot),
+                );
+            }
+
+            // Step around a bit, when idle for too long
+            if let Some(secs) = OPTS.idle_wander_secs {
+                let mut idle_since = bot_state.idle_since.lock();
+                let mut wander_home = bot_state.wander_home.lock();
+                let busy =
+                    bot_state.pathfinding_requested_by.lock().is_some() || is_pathfinding(&bot);
+                if busy {
+                    *idle_since = None;
+                } else if let Some(home) = wander_home.take() {
+                    info!("Stepping back to {home:?}...");
+                    goto_block(&mut bot, &bot_state, home);
+                    *idle_since = None;
+                } else if idle_since.get_or_insert_with(Instant::now).elapsed()
+                    > Duration::from_secs(secs)
+                {
+                    let pos = bot.entity_component::<Position>(bot.entity);
+                    let here = BlockPos {
+                        x: pos.x.floor() as i32,
+                        y: pos.y.floor() as i32,
+                        z: pos.z.floor() as i32,
+                    };
+                    if let Some(step_to) = random_adjacent_walkable_block(&bot, here) {
+                        info!("Idle for {secs} seconds. Stepping to {step_to:?} and back...");
+                        goto_block(&mut bot, &bot_state, step_to);
+                        *wander_home = Some(here);
                     }
+                    *idle_since = None;
                 }
             }
 
             let mut pathfinding_requested_by = bot_state.pathfinding_requested_by.lock();
+
+            // Give up on requests, that got stuck
+            if let Some(deadline_secs) = OPTS.task_deadline_secs
+                && let Some(requesting_player) = pathfinding_requested_by.clone()
+                && bot_state
+                    .pathfinding_requested_at
+                    .lock()
+                    .is_some_and(|at| at.elapsed() > Duration::from_secs(deadline_secs))
+            {
+                warn!("Request of {requesting_player} is taking more than {deadline_secs} seconds. Cancelling it!");
+                bot.ecs.lock().send_event(StopPathfindingEvent {
+                    entity: bot.entity,
+                    force: true,
+                });
+                *pathfinding_requested_by = None;
+                let was_pull = bot_state.pending_flip.lock().take().is_none();
+                if was_pull && !OPTS.stasis_dry_run {
+                    bot_state.record_pull_stat(|stats| stats.cancelled += 1);
+                }
+                *bot_state.return_to_after_pulled.lock() = None;
+                commands::send_command(
+                    &mut bot,
+                    &bot_state,
+                    &format!(
+                        "msg {requesting_player} Sorry, I got stuck and gave up. Please try again."
+                    ),
+                );
+            }
+
             if let Some(ref requesting_player) = *pathfinding_requested_by {
                 let mut ecs = bot.ecs.lock();
                 let pathfinder: &Pathfinder = ecs
@@ -746,36 +1584,62 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                     .get_mut(&mut *ecs, bot.entity)
                     .unwrap();
 
-                if !pathfinder.is_calculating && pathfinder.goal.is_none() {
+                // Wait with interacting, until the server stops moving us back
+                if !pathfinder.is_calculating
+                    && pathfinder.goal.is_none()
+                    && !bot_state.is_rubber_banding()
+                {
                     drop(ecs);
 
-                    if let Some(trapdoor_pos) = bot_state
-                        .remembered_trapdoor_positions
-                        .lock()
-                        .remove(requesting_player)
-                    {
-                        if !OPTS.quiet {
-                            bot.send_command_packet(&format!(
-                                "msg {requesting_player} Welcome back, {requesting_player}!"
-                            ));
+                    // Keep the chamber remembered, when not actually pulling
+                    let trapdoor_pos = if bot_state.pending_flip.lock().is_some() {
+                        None
+                    } else if OPTS.stasis_dry_run {
+                        bot_state
+                            .remembered_trapdoor_positions
+                            .lock()
+                            .get(requesting_player)
+                            .copied()
+                    } else {
+                        bot_state
+                            .remembered_trapdoor_positions
+                            .lock()
+                            .remove(requesting_player)
+                    };
+
+                    if let Some(flip_pos) = bot_state.pending_flip.lock().take() {
+                        let coords = format!("{} {} {}", flip_pos.x, flip_pos.y, flip_pos.z);
+                        let message = if OPTS.stasis_dry_run {
+                            info!("Dry run: Would flip block at {flip_pos:?} for {requesting_player} now.");
+                            format!("Dry run: I would have flipped the block at {coords} now.")
+                        } else {
+                            info!("Flipping block at {flip_pos:?} for {requesting_player}...");
+                            interact_with_block(&bot, flip_pos);
+                            format!("Flipped block at {coords}.")
+                        };
+                        commands::send_command(
+                            &mut bot,
+                            &bot_state,
+                            &format!("msg {requesting_player} {message}"),
+                        );
+                        *pathfinding_requested_by = None;
+                    } else if let Some(trapdoor_pos) = trapdoor_pos {
+                        if OPTS.stasis_dry_run {
+                            info!("Dry run: Would flip trapdoor at {trapdoor_pos:?} for {requesting_player} now.");
+                            commands::send_command(
+                                &mut bot,
+                                &bot_state,
+                                &format!("msg {requesting_player} Dry run: I would have pulled your pearl now."),
+                            );
+                        } else {
+                            if !bot_state.is_quiet() {
+                                bot.send_command_packet(&format!(
+                                    "msg {requesting_player} Welcome back, {requesting_player}!"
+                                ));
+                            }
+                            interact_with_block(&bot, trapdoor_pos);
+                            bot_state.record_pull_stat(|stats| stats.succeeded += 1);
                         }
-                        bot.ecs.lock().send_event(SendPacketEvent {
-                            entity: bot.entity,
-                            packet: ServerboundGamePacket::UseItemOn(ServerboundUseItemOnPacket {
-                                block_hit: BlockHit {
-                                    block_pos: azalea::BlockPos::from(trapdoor_pos),
-                                    direction: Direction::Down,
-                                    location: azalea::Vec3 {
-                                        x: trapdoor_pos.x as f64 + 0.5,
-                                        y: trapdoor_pos.y as f64 + 0.5,
-                                        z: trapdoor_pos.z as f64 + 0.5,
-                                    },
-                                    inside: true,
-                                },
-                                hand: InteractionHand::MainHand,
-                                sequence: 0,
-                            }),
-                        });
 
                         *pathfinding_requested_by = None;
                         if let Some(return_to_after_pulled) =
@@ -787,11 +1651,7 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
                                 y: return_to_after_pulled.y.floor() as i32,
                                 z: return_to_after_pulled.z.floor() as i32,
                             });
-                            if OPTS.no_mining {
-                                bot.goto_without_mining(goal);
-                            } else {
-                                bot.goto(goal);
-                            }
+                            goto(&mut bot, &bot_state, goal);
                         }
 
                         let bot_state = bot_state.clone();
@@ -813,6 +1673,310 @@ async fn handle(mut bot: Client, event: Event, mut bot_state: BotState) -> anyho
     Ok(())
 }
 
+/// Pull for the closest player, when --pull-button changed from unpowered to powered.
+fn handle_pull_button_update(
+    bot: &mut Client,
+    bot_state: &BotState,
+    block_pos: BlockPos,
+    block_state: BlockState,
+) -> anyhow::Result<()> {
+    let Some(button_pos) = OPTS.pull_button else {
+        return Ok(());
+    };
+    if OPTS.no_stasis || block_pos != button_pos {
+        return Ok(());
+    }
+
+    // Only react to getting pressed, not to getting released (or a lever turned off)
+    let powered = block_state.property::<Powered>().unwrap_or(false);
+    let was_powered = std::mem::replace(&mut *bot_state.pull_button_powered.lock(), powered);
+    if powered && !was_powered {
+        if let Some(player) = closest_player_to(bot, button_pos, OPTS.pull_button_radius) {
+            info!("{player} activated the pull button at {button_pos:?}.");
+            execute_rate_limited(bot, bot_state, player, "tp".to_owned(), vec![])?;
+        } else {
+            warn!("The pull button at {button_pos:?} got activated, but no player is close enough to it.");
+        }
+    }
+    Ok(())
+}
+
+/// Execute a command, unless the last one was handled less than a second ago (to avoid getting spam kicked).
+fn execute_rate_limited(
+    bot: &mut Client,
+    bot_state: &BotState,
+    sender: String,
+    command: String,
+    args: Vec<String>,
+) -> anyhow::Result<()> {
+    if bot_state
+        .last_dm_handled_at
+        .lock()
+        .map(|at| at.elapsed() > Duration::from_secs(1))
+        .unwrap_or(true)
+    {
+        info!("Executing command {command:?} sent by {sender:?} with args {args:?}");
+        if commands::execute(bot, bot_state, sender, command, args).context("Executing command")? {
+            *bot_state.last_dm_handled_at.lock() = Some(Instant::now());
+        } else {
+            warn!("Command was not executed. Most likely an unknown command.");
+        }
+    } else {
+        warn!("Last command was handled less than a second ago. Ignoring command from {sender:?} to avoid getting spam kicked.");
+    }
+    Ok(())
+}
+
+/// Check whether an ender pearl can be seen in the stasis chamber of the given trapdoor.
+/// Returns None, if the trapdoor is not loaded and thus can't be checked.
+fn is_pearl_visible_at(bot: &Client, trapdoor_pos: BlockPos) -> Option<bool> {
+    bot.world()
+        .read()
+        .get_block_state(&azalea::BlockPos::from(trapdoor_pos))?;
+
+    let trapdoor_center = Vec3::new(
+        trapdoor_pos.x as f64 + 0.5,
+        trapdoor_pos.y as f64 + 0.5,
+        trapdoor_pos.z as f64 + 0.5,
+    );
+    let mut query = bot
+        .ecs
+        .lock()
+        .query_filtered::<&Position, With<EnderPearl>>();
+    let found = query.iter(&bot.ecs.lock()).any(|pos| {
+        // Same search area as used for finding the trapdoor of a thrown pearl
+        (pos.x - trapdoor_center.x).abs() <= 1.0
+            && (pos.z - trapdoor_center.z).abs() <= 1.0
+            && (pos.y - trapdoor_center.y).abs() <= 16.0
+    });
+    Some(found)
+}
+
+/// Name of the closest other player within max_dist blocks of the given block.
+fn closest_player_to(bot: &Client, block_pos: BlockPos, max_dist: f64) -> Option<String> {
+    let my_entity_id = bot.entity_component::<MinecraftEntityId>(bot.entity).0;
+    let block_center = Vec3::new(
+        block_pos.x as f64 + 0.5,
+        block_pos.y as f64 + 0.5,
+        block_pos.z as f64 + 0.5,
+    );
+    let mut query = bot
+        .ecs
+        .lock()
+        .query_filtered::<(&Position, &GameProfileComponent, &MinecraftEntityId), With<Player>>();
+    query
+        .iter(&bot.ecs.lock())
+        .filter(|(_, _, entity_id)| entity_id.0 != my_entity_id)
+        .map(|(pos, profile, _)| (pos.distance_to_sqr(&block_center), profile.name.clone()))
+        .filter(|(dist_sqr, _)| *dist_sqr <= max_dist * max_dist)
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, name)| name)
+}
+
+fn is_food(item: &ItemSlotData) -> bool {
+    item.components
+        .get(azalea::registry::DataComponentKind::Food)
+        .is_some()
+        || FOOD_ITEMS.contains(&item.kind)
+}
+
+/// Find the first hotbar slot with an item matching the predicate.
+/// Returns the hotbar slot and a description of the item.
+fn find_in_hotbar(bot: &Client, predicate: impl Fn(&ItemSlotData) -> bool) -> Option<(u8, String)> {
+    let inv = bot.entity_component::<InventoryComponent>(bot.entity);
+    let inv_menu = inv.inventory_menu;
+    for (hotbar_slot, slot) in inv_menu.hotbar_slots_range().enumerate() {
+        if let Some(ItemSlot::Present(item_slot)) = inv_menu.slot(slot)
+            && predicate(item_slot)
+        {
+            return Some((
+                hotbar_slot as u8,
+                format!("{} ({}x)", item_slot.kind, item_slot.count),
+            ));
+        }
+    }
+    None
+}
+
+/// Saturation restored by eating the item (vanilla values). Unknown food ranks lowest.
+fn food_saturation(kind: Item) -> f32 {
+    match kind {
+        Item::GoldenCarrot => 14.4,
+        Item::CookedBeef | Item::CookedPorkchop => 12.8,
+        Item::RabbitStew => 12.0,
+        Item::GoldenApple | Item::EnchantedGoldenApple => 9.6,
+        Item::CookedMutton | Item::CookedSalmon => 9.6,
+        Item::CookedChicken | Item::MushroomStew | Item::BeetrootSoup => 7.2,
+        Item::BakedPotato | Item::Bread | Item::CookedRabbit | Item::CookedCod => 6.0,
+        Item::PumpkinPie => 4.8,
+        Item::Carrot => 3.6,
+        Item::Apple => 2.4,
+        Item::Beef | Item::Porkchop | Item::Rabbit => 1.8,
+        Item::MelonSlice | Item::Beetroot | Item::Mutton | Item::Chicken => 1.2,
+        Item::Potato | Item::DriedKelp => 0.6,
+        Item::SweetBerries | Item::GlowBerries | Item::Cod | Item::Salmon | Item::Cookie => 0.4,
+        Item::TropicalFish => 0.2,
+        _ => 0.0,
+    }
+}
+
+/// Find the food in the hotbar, that restores the most saturation.
+/// Golden apples are too valuable and only picked, if there is nothing else.
+fn find_best_food_in_hotbar(bot: &Client) -> Option<(u8, String)> {
+    let inv = bot.entity_component::<InventoryComponent>(bot.entity);
+    let inv_menu = inv.inventory_menu;
+    let mut best = None;
+    let mut best_rank = (false, f32::MIN);
+    for (hotbar_slot, slot) in inv_menu.hotbar_slots_range().enumerate() {
+        if let Some(ItemSlot::Present(item_slot)) = inv_menu.slot(slot)
+            && is_food(item_slot)
+        {
+            let is_ordinary = !matches!(
+                item_slot.kind,
+                Item::GoldenApple | Item::EnchantedGoldenApple
+            );
+            let rank = (is_ordinary, food_saturation(item_slot.kind));
+            if best.is_none() || rank > best_rank {
+                best_rank = rank;
+                best = Some((
+                    hotbar_slot as u8,
+                    format!("{} ({}x)", item_slot.kind, item_slot.count),
+                ));
+            }
+        }
+    }
+    best
+}
+
+/// Disconnect from the server and give it a moment to register the logout, before quitting.
+/// Quits in the background, so the disconnect can still be sent out meanwhile.
+pub fn disconnect_and_exit(bot: &mut Client, exit_code: i32) {
+    QUITTING.store(true, Ordering::SeqCst);
+    bot.disconnect();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(OPTS.disconnect_wait_ms)).await;
+        std::process::exit(exit_code);
+    });
+}
+
+/// Start pathfinding to the goal (respecting --no-mining). Gets out of any vehicle first.
+pub fn goto(bot: &mut Client, bot_state: &BotState, goal: impl Goal + Send + Sync + 'static) {
+    if let Some(vehicle_id) = bot_state.riding_vehicle.lock().take() {
+        info!("Dismounting vehicle {vehicle_id} before walking...");
+        bot.ecs.lock().send_event(SendPacketEvent {
+            entity: bot.entity,
+            packet: ServerboundGamePacket::PlayerInput(ServerboundPlayerInputPacket {
+                xxa: 0.0,
+                zza: 0.0,
+                is_jumping: false,
+                is_shift_key_down: true,
+            }),
+        });
+    }
+
+    if OPTS.no_mining {
+        bot.goto_without_mining(goal);
+    } else {
+        bot.goto(goal);
+    }
+}
+
+/// Walk onto the given block.
+fn goto_block(bot: &mut Client, bot_state: &BotState, block_pos: BlockPos) {
+    goto(
+        bot,
+        bot_state,
+        BlockPosGoal(azalea::BlockPos::from(block_pos)),
+    );
+}
+
+/// Pick a random horizontally adjacent block, that can be stood on.
+fn random_adjacent_walkable_block(bot: &Client, block_pos: BlockPos) -> Option<BlockPos> {
+    let world = bot.world();
+    let world = world.read();
+    let is_air = |x: i32, y: i32, z: i32| {
+        world
+            .get_block_state(&azalea::BlockPos::new(x, y, z))
+            .is_some_and(|state| state.is_air())
+    };
+    let is_solid = |x: i32, y: i32, z: i32| {
+        world
+            .get_block_state(&azalea::BlockPos::new(x, y, z))
+            .is_some_and(|state| !state.is_air())
+    };
+    let candidates = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+        .into_iter()
+        .map(|(x_offset, z_offset)| BlockPos {
+            x: block_pos.x + x_offset,
+            y: block_pos.y,
+            z: block_pos.z + z_offset,
+        })
+        .filter(|pos| {
+            is_air(pos.x, pos.y, pos.z)
+                && is_air(pos.x, pos.y + 1, pos.z)
+                && is_solid(pos.x, pos.y - 1, pos.z)
+        })
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return None;
+    }
+    // Good enough randomness for picking a direction
+    let random = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos() as usize;
+    Some(candidates[random % candidates.len()])
+}
+
+/// React to low health or a totem pop, as configured by --autolog-mode.
+fn autolog(bot: &mut Client) {
+    match OPTS.autolog_mode {
+        AutologMode::Exit => {
+            warn!("Disconnecting and quitting...");
+            disconnect_and_exit(bot, EXITCODE_LOW_HEALTH_OR_TOTEM_POP);
+        }
+        AutologMode::Disconnect => {
+            warn!(
+                "Disconnecting and rejoining in {} seconds...",
+                OPTS.autolog_reconnect_secs
+            );
+            *RECONNECT_NOT_BEFORE.lock() =
+                Some(Instant::now() + Duration::from_secs(OPTS.autolog_reconnect_secs));
+            bot.disconnect();
+        }
+    }
+}
+
+fn is_pathfinding(bot: &Client) -> bool {
+    let mut ecs = bot.ecs.lock();
+    let pathfinder: &Pathfinder = ecs
+        .query::<&Pathfinder>()
+        .get_mut(&mut *ecs, bot.entity)
+        .unwrap();
+    pathfinder.goal.is_some()
+}
+
+/// Right-click the center of the given block (e.g. to open/close a trapdoor).
+fn interact_with_block(bot: &Client, block_pos: BlockPos) {
+    bot.ecs.lock().send_event(SendPacketEvent {
+        entity: bot.entity,
+        packet: ServerboundGamePacket::UseItemOn(ServerboundUseItemOnPacket {
+            block_hit: BlockHit {
+                block_pos: azalea::BlockPos::from(block_pos),
+                direction: Direction::Down,
+                location: azalea::Vec3 {
+                    x: block_pos.x as f64 + 0.5,
+                    y: block_pos.y as f64 + 0.5,
+                    z: block_pos.z as f64 + 0.5,
+                },
+                inside: true,
+            },
+            hand: InteractionHand::MainHand,
+            sequence: 0,
+        }),
+    });
+}
+
 #[derive(Clone, Component, Resource)]
 pub struct SwarmState {
     last_account_refresh: Arc<Mutex<Instant>>,
@@ -827,8 +1991,12 @@ impl Default for SwarmState {
 }
 
 async fn swarm_rejoin(mut swarm: Swarm, state: SwarmState, account: Account, join_opts: JoinOpts) {
-    let mut reconnect_after_secs = 5;
+    // Kept as float, so small multipliers still make the delay grow
+    let mut reconnect_after_secs = RECONNECT_CONFIG.lock().initial_delay_secs as f64;
     loop {
+        if QUITTING.load(Ordering::SeqCst) {
+            return;
+        }
         let last_refreshed = state.last_account_refresh.lock().elapsed();
         if last_refreshed > Duration::from_secs(/*3h*/ 60 * 60 * 3)
             && let Some(access_token) = account.access_token.clone()
@@ -848,18 +2016,45 @@ async fn swarm_rejoin(mut swarm: Swarm, state: SwarmState, account: Account, joi
             }
         }
 
-        info!("Reconnecting after {} seconds...", reconnect_after_secs);
+        if let Some(not_before) = RECONNECT_NOT_BEFORE.lock().take() {
+            let wait = not_before.saturating_duration_since(Instant::now());
+            info!(
+                "Waiting {} seconds, before trying to reconnect...",
+                wait.as_secs()
+            );
+            tokio::time::sleep(wait).await;
+        }
 
-        tokio::time::sleep(Duration::from_secs(reconnect_after_secs)).await;
-        reconnect_after_secs = (reconnect_after_secs * 2).min(60 * 30); // 2x or max 30 minutes
+        info!(
+            "Reconnecting after {} seconds...",
+            reconnect_after_secs.round()
+        );
+
+        tokio::time::sleep(Duration::from_secs(reconnect_after_secs.round() as u64)).await;
+        {
+            let reconnect_config = RECONNECT_CONFIG.lock();
+            reconnect_after_secs = (reconnect_after_secs * reconnect_config.multiplier)
+                .min(reconnect_config.max_delay_secs as f64);
+        }
 
+        if QUITTING.load(Ordering::SeqCst) {
+            return;
+        }
         info!("Joining again...");
         match swarm
             .add_with_opts(&account, state.clone(), &join_opts)
             .await
         {
-            Ok(_) => return,
-            Err(join_err) => error!("Failed to rejoin: {join_err}"), // Keep rejoining
+            Ok(_) => {
+                let mut reconnect_stats = RECONNECT_STATS.lock();
+                reconnect_stats.total_reconnects += 1;
+                reconnect_stats.consecutive_failures = 0;
+                return;
+            }
+            Err(join_err) => {
+                error!("Failed to rejoin: {join_err}"); // Keep rejoining
+                RECONNECT_STATS.lock().consecutive_failures += 1;
+            }
         }
     }
 }